@@ -0,0 +1,464 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use isa::{decode, opcodes, Decoded, OperandKind};
+
+use crate::parser::{strip_comment, trim_with_offset};
+use crate::preprocessor::first_word;
+use crate::{Diagnostic, ListingLine, Output, Severity, SourceLine, Span, Word};
+
+/// What an instruction does to the control flow.
+#[derive(Clone, Copy)]
+enum Flow {
+    /// Continues with the next instruction
+    Next,
+    Halt,
+    Jump(Word),
+    /// Continues at the target or with the next instruction
+    Branch(Word),
+    /// Continues with the next instruction once the routine returns, the target of `CALLR`
+    /// isn't known
+    Call(Option<Word>),
+    Return,
+}
+
+impl Flow {
+    fn of(decoded: &Decoded) -> Flow {
+        match decoded.instruction.opcode {
+            opcodes::HALT => Flow::Halt,
+            opcodes::JMP => Flow::Jump(decoded.operands[0]),
+            opcodes::CALL => Flow::Call(Some(decoded.operands[0])),
+            opcodes::CALLR => Flow::Call(None),
+            opcodes::RET => Flow::Return,
+            // every branch ends with its target
+            _ if decoded.instruction.mnemonic.starts_with("BR") => {
+                Flow::Branch(decoded.operands[decoded.operands.len() - 1])
+            }
+            _ => Flow::Next,
+        }
+    }
+}
+
+/// What is at an address of the image.
+enum Place {
+    /// The start of an instruction
+    Code,
+    /// Data of the listing line
+    Data(usize),
+    /// The middle of the instruction at the address
+    Inside(Word),
+    /// Bytes that no line emitted, like the gap left by `.org`
+    Gap,
+    Outside,
+}
+
+struct Code {
+    /// Index of the line in the listing
+    index: usize,
+    decoded: Decoded,
+}
+
+struct Analysis<'a> {
+    output: &'a Output,
+    code: BTreeMap<Word, Code>,
+    /// Start, end and listing line of every run of data
+    data: Vec<(Word, Word, usize)>,
+    /// Warnings with the listing line they are about, to sort them in source order
+    warnings: Vec<(usize, Diagnostic)>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(output: &'a Output) -> Self {
+        let mut code = BTreeMap::new();
+        let mut data = Vec::new();
+        for (index, line) in output.listing.iter().enumerate() {
+            if line.bytes.is_empty() {
+                continue;
+            }
+
+            // directives emit data, everything else is an instruction
+            let decoded = match first_word(&line.text).0.starts_with('.') {
+                true => None,
+                false => decode(&line.bytes)
+                    .ok()
+                    .filter(|decoded| decoded.size() == line.bytes.len()),
+            };
+            match decoded {
+                Some(decoded) => {
+                    code.insert(line.address, Code { index, decoded });
+                }
+                None => data.push((line.address, line.address + line.bytes.len() as Word, index)),
+            }
+        }
+
+        Analysis {
+            output,
+            code,
+            data,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn diagnostic(&self, index: usize, severity: Severity, message: String) -> Diagnostic {
+        let line: &ListingLine = &self.output.listing[index];
+        let (column, text) = trim_with_offset(strip_comment(&line.text), 0);
+        let source = SourceLine {
+            file: &line.file,
+            line: line.line,
+            text: &line.text,
+            context: &[],
+        };
+        source.diagnostic(severity, Span::new(column, column + text.len()), message)
+    }
+
+    fn warn(&mut self, index: usize, message: String, notes: Vec<(usize, String)>) {
+        let mut warning = self.diagnostic(index, Severity::Warning, message);
+        for (index, message) in notes {
+            warning = warning.with_note(self.diagnostic(index, Severity::Note, message));
+        }
+        self.warnings.push((index, warning));
+    }
+
+    /// The label at the address or the address itself.
+    fn describe(&self, address: Word) -> String {
+        match self
+            .output
+            .symbols
+            .iter()
+            .find(|symbol| !symbol.constant && symbol.value == address)
+        {
+            Some(symbol) => format!("`{}`", symbol.name),
+            None => format!("0x{:08X}", address),
+        }
+    }
+
+    fn place(&self, address: Word) -> Place {
+        if self.code.contains_key(&address) {
+            return Place::Code;
+        }
+        if let Some((_, _, index)) = self
+            .data
+            .iter()
+            .find(|(start, end, _)| (*start..*end).contains(&address))
+        {
+            return Place::Data(*index);
+        }
+        if let Some((start, code)) = self.code.range(..address).next_back() {
+            if address < start + code.decoded.size() as Word {
+                return Place::Inside(*start);
+            }
+        }
+
+        let end = self.output.base as u64 + self.output.image.len() as u64;
+        match address >= self.output.base && (address as u64) < end {
+            true => Place::Gap,
+            false => Place::Outside,
+        }
+    }
+
+    /// Warns about a target of a jump, branch or call that isn't an instruction.
+    fn check_target(&mut self, index: usize, kind: &str, target: Word) {
+        let described = self.describe(target);
+        match self.place(target) {
+            Place::Code => {}
+            Place::Data(data) => self.warn(
+                index,
+                format!("{} target {} is inside data", kind, described),
+                vec![(data, "data defined here".to_string())],
+            ),
+            Place::Inside(start) => self.warn(
+                index,
+                format!(
+                    "{} target {} is in the middle of an instruction",
+                    kind, described
+                ),
+                vec![(
+                    self.code[&start].index,
+                    format!("instruction starts at 0x{:08X}", start),
+                )],
+            ),
+            Place::Gap => self.warn(
+                index,
+                format!("{} target {} is not an instruction", kind, described),
+                Vec::new(),
+            ),
+            Place::Outside => self.warn(
+                index,
+                format!("{} target {} is outside of the image", kind, described),
+                Vec::new(),
+            ),
+        }
+    }
+
+    /// Warns if execution continues from the instruction at `address` into something that
+    /// isn't an instruction.
+    fn check_next(&mut self, address: Word) {
+        let code = &self.code[&address];
+        let (index, next) = (code.index, address + code.decoded.size() as Word);
+        match self.place(next) {
+            Place::Code => {}
+            Place::Data(data) => self.warn(
+                index,
+                "execution continues into data".to_string(),
+                vec![(data, "data defined here".to_string())],
+            ),
+            Place::Outside => self.warn(
+                index,
+                "execution continues past the end of the image".to_string(),
+                Vec::new(),
+            ),
+            Place::Inside(_) | Place::Gap => self.warn(
+                index,
+                format!(
+                    "execution continues at 0x{:08X}, which is not an instruction",
+                    next
+                ),
+                Vec::new(),
+            ),
+        }
+    }
+
+    /// Number of arguments `RET` drops for the call at the address, the count has to be
+    /// pushed right before the call.
+    fn argument_count(&self, address: Word) -> Option<Word> {
+        let (start, code) = self.code.range(..address).next_back()?;
+        match code.decoded.instruction.opcode == opcodes::PUSH
+            && start + code.decoded.size() as Word == address
+        {
+            true => Some(code.decoded.operands[0]),
+            false => None,
+        }
+    }
+
+    /// Words the `.local`s of a procedure push at its start, they are dropped by `RET`.
+    fn locals(&self, root: Word) -> i64 {
+        self.output
+            .locals
+            .iter()
+            .find(|(address, _)| *address == root)
+            .map_or(0, |(_, locals)| *locals as i64)
+    }
+
+    /// Targets of calls and labels used as values, which may be called with `CALLR`.
+    fn routines(&self) -> BTreeSet<Word> {
+        let labels: HashSet<Word> = self
+            .output
+            .symbols
+            .iter()
+            .filter(|symbol| !symbol.constant)
+            .map(|symbol| symbol.value)
+            .collect();
+
+        let mut routines = BTreeSet::new();
+        for code in self.code.values() {
+            let decoded = &code.decoded;
+            if let Flow::Call(Some(target)) = Flow::of(decoded) {
+                routines.insert(target);
+            }
+            let operands = decoded.instruction.operands.iter().zip(&decoded.operands);
+            for (_, value) in operands.filter(|(kind, _)| **kind == OperandKind::Immediate) {
+                if labels.contains(value) {
+                    routines.insert(*value);
+                }
+            }
+        }
+
+        routines.retain(|address| self.code.contains_key(address));
+        routines
+    }
+
+    /// Follows every path from `root` up to `HALT` or `RET`, counting the words on the stack.
+    /// `routine` is false for the entry point, which wasn't called.
+    fn walk(&mut self, root: Word, routine: bool, reached: &mut HashSet<Word>) {
+        let name = self.describe(root);
+        let locals = if routine { self.locals(root) } else { 0 };
+
+        // words pushed since the root when an instruction was first reached, `None` once it
+        // can't be known anymore
+        let mut depths: HashMap<Word, Option<i64>> = HashMap::new();
+        let mut pending: Vec<(Option<usize>, Word, Option<i64>)> = vec![(None, root, Some(0))];
+        while let Some((from, address, depth)) = pending.pop() {
+            if let Some(&seen) = depths.get(&address) {
+                if let (Some(seen), Some(depth), Some(from)) = (seen, depth, from) {
+                    if seen != depth {
+                        let message = format!(
+                            "unbalanced stack, {} is reached with {} word(s) pushed here and {} on another path",
+                            self.describe(address),
+                            depth,
+                            seen
+                        );
+                        self.warn(from, message, Vec::new());
+                    }
+                }
+                continue;
+            }
+            depths.insert(address, depth);
+            reached.insert(address);
+
+            let code = &self.code[&address];
+            let (index, flow) = (code.index, Flow::of(&code.decoded));
+            let opcode = code.decoded.instruction.opcode;
+            let next = address + code.decoded.size() as Word;
+
+            let depth = match (opcode, depth) {
+                (opcodes::PUSH, Some(depth)) | (opcodes::PUSHR, Some(depth)) => Some(depth + 1),
+                (opcodes::POP, Some(0)) => {
+                    let message = match routine {
+                        true => format!(
+                            "`POP` below the frame of {}, it takes a word saved by `CALL`",
+                            name
+                        ),
+                        false => "`POP` with nothing pushed, the stack underflows".to_string(),
+                    };
+                    self.warn(index, message, Vec::new());
+                    None
+                }
+                (opcodes::POP, Some(depth)) => Some(depth - 1),
+                (opcodes::CALL, Some(depth)) | (opcodes::CALLR, Some(depth)) => {
+                    match self.argument_count(address) {
+                        Some(count) if depth > count as i64 => Some(depth - count as i64 - 1),
+                        Some(count) => {
+                            let message = format!(
+                                "`RET` drops {} argument(s) and their count, but only {} word(s) are pushed",
+                                count, depth
+                            );
+                            self.warn(index, message, Vec::new());
+                            None
+                        }
+                        // the missing count is a warning of its own, the rest of the routine
+                        // is still worth checking
+                        None => Some(depth),
+                    }
+                }
+                (opcodes::RET, _) if !routine => {
+                    self.warn(
+                        index,
+                        "`RET` is reachable without a `CALL`".to_string(),
+                        Vec::new(),
+                    );
+                    None
+                }
+                (opcodes::RET, Some(depth)) if depth != locals => {
+                    let message = match depth > locals {
+                        true => format!(
+                            "unbalanced stack, {} word(s) pushed in {} are never popped",
+                            depth - locals,
+                            name
+                        ),
+                        false => format!(
+                            "unbalanced stack, {} word(s) more popped than pushed in {}",
+                            locals - depth,
+                            name
+                        ),
+                    };
+                    self.warn(index, message, Vec::new());
+                    None
+                }
+                (_, depth) => depth,
+            };
+
+            let targets = match flow {
+                Flow::Halt | Flow::Return => vec![],
+                Flow::Jump(target) => vec![target],
+                Flow::Branch(target) => vec![target, next],
+                Flow::Next | Flow::Call(_) => vec![next],
+            };
+            if !matches!(flow, Flow::Halt | Flow::Return | Flow::Jump(_)) {
+                self.check_next(address);
+            }
+            for target in targets {
+                if self.code.contains_key(&target) {
+                    pending.push((Some(index), target, depth));
+                }
+            }
+        }
+    }
+
+    fn check(mut self) -> Vec<Diagnostic> {
+        // targets and argument counts are checked even if the instruction is never reached
+        let addresses: Vec<Word> = self.code.keys().copied().collect();
+        for &address in &addresses {
+            let index = self.code[&address].index;
+            match Flow::of(&self.code[&address].decoded) {
+                Flow::Jump(target) => self.check_target(index, "jump", target),
+                Flow::Branch(target) => self.check_target(index, "branch", target),
+                Flow::Call(target) => {
+                    if let Some(target) = target {
+                        self.check_target(index, "call", target);
+                    }
+                    if self.argument_count(address).is_none() {
+                        self.warn(
+                            index,
+                            "no argument count is pushed right before the call, `RET` pops a word it doesn't own".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut reached = HashSet::new();
+        let entry = self.output.entry;
+        match self.place(entry) {
+            Place::Code => self.walk(entry, false, &mut reached),
+            Place::Data(index) => self.warn(
+                index,
+                "execution starts inside data".to_string(),
+                Vec::new(),
+            ),
+            _ => {}
+        }
+        for routine in self.routines() {
+            self.walk(routine, true, &mut reached);
+        }
+
+        // one warning for every run of unreachable instructions, the end of the last one
+        // continues the run
+        let mut run_end = None;
+        for &address in &addresses {
+            let code = &self.code[&address];
+            let (index, end) = (code.index, address + code.decoded.size() as Word);
+            if reached.contains(&address) {
+                run_end = None;
+                continue;
+            }
+            if run_end != Some(address) {
+                let mut notes = Vec::new();
+                if let Some((start, before)) = self.code.range(..address).next_back() {
+                    let stops = matches!(
+                        Flow::of(&before.decoded),
+                        Flow::Halt | Flow::Jump(_) | Flow::Return
+                    );
+                    if stops && start + before.decoded.size() as Word == address {
+                        notes.push((
+                            before.index,
+                            format!(
+                                "execution doesn't continue after this `{}`",
+                                before.decoded.instruction.mnemonic
+                            ),
+                        ));
+                    }
+                }
+                self.warn(index, "unreachable code".to_string(), notes);
+            }
+            run_end = Some(end);
+        }
+
+        // paths through the same instruction and repeated lines, like the ones of a macro, find
+        // the same problems
+        self.warnings.sort_by_key(|(index, _)| *index);
+        let mut warnings: Vec<Diagnostic> = Vec::new();
+        for (_, warning) in self.warnings {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+        warnings
+    }
+}
+
+/// Builds the control flow graph of the instructions in the listing and warns about
+/// unreachable code, paths leaving the code, unbalanced stacks and bad jump targets.
+pub fn check(output: &Output) -> Vec<Diagnostic> {
+    Analysis::new(output).check()
+}
@@ -0,0 +1,980 @@
+use std::fs;
+
+use crate::directives::Data;
+use crate::expression::Expr;
+use crate::layout::default_rank;
+use crate::object::{Object, ObjectSection, ObjectSymbol, Relocation, SymbolValue, Target};
+use crate::parser::{parse_line, Operand, OperandKind, Statement};
+use crate::preprocessor::{find_file, first_word, preprocess, Line, Procedure};
+use crate::scope::Scope;
+use crate::symbols::{Base, Relocatable, SymbolTable, Value};
+use crate::{
+    Byte, DefinedSymbol, Diagnostic, ListingLine, ObjectOutput, Options, Output, Section,
+    SourceLine, Span, Word,
+};
+
+/// Whether `value` fits into `width` bytes, either unsigned or as a negative two's complement number.
+pub fn fits(value: Word, width: usize) -> bool {
+    let bits = width as u32 * 8;
+    bits >= Word::BITS || value >> bits == 0 || (value as i32) >> (bits - 1) == -1
+}
+
+/// Evaluates an operand and checks that it fits into `width` bytes. In object files the value
+/// may be relative to a base only known after linking, the linker checks its width.
+fn resolve_relocatable(
+    operand: &Operand,
+    width: usize,
+    kind: &str,
+    line: &SourceLine,
+    symbols: &mut SymbolTable,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Relocatable {
+    let relocatable = symbols.relocatable;
+    let value = match &operand.kind {
+        OperandKind::Register(value) => Relocatable::absolute(*value),
+        OperandKind::Value(expr) => symbols
+            .evaluate_relocatable(expr, line, operand.span)
+            .and_then(|value| match relocatable {
+                true => Ok(value),
+                false => value
+                    .into_absolute(line, operand.span)
+                    .map(Relocatable::absolute),
+            })
+            .unwrap_or_else(|diagnostic| {
+                diagnostics.push(diagnostic);
+                Relocatable::absolute(0)
+            }),
+    };
+
+    // the encoder truncates values to their width
+    if value.base.is_none() && !fits(value.value, width) {
+        diagnostics.push(line.error(
+            operand.span,
+            format!(
+                "value 0x{:X} does not fit into the {}-byte {} operand",
+                value.value, width, kind
+            ),
+        ));
+    }
+
+    value
+}
+
+/// Evaluates an operand that has to be known while assembling and checks that it fits into `width` bytes.
+fn resolve(
+    operand: &Operand,
+    width: usize,
+    kind: &str,
+    line: &SourceLine,
+    symbols: &mut SymbolTable,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Word {
+    resolve_relocatable(operand, width, kind, line, symbols, diagnostics)
+        .into_absolute(line, operand.span)
+        .unwrap_or_else(|diagnostic| {
+            diagnostics.push(diagnostic);
+            0
+        })
+}
+
+/// Fixed start address of a section and where it was set.
+struct Origin<'a> {
+    address: Word,
+    line: SourceLine<'a>,
+    span: Span,
+    /// Set by the layout file instead of `.org`
+    layout: bool,
+}
+
+/// A section during assembly. Labels in it are offsets until the section is placed.
+struct SectionState<'a> {
+    name: String,
+    /// Only reserves space, see [`Section::bss`]
+    bss: bool,
+    origin: Option<Origin<'a>>,
+    /// Current offset, the size of the section after the first pass
+    size: Word,
+    /// The start of the section is aligned to every `.align` inside of it
+    alignment: Word,
+    /// Where the section was used first, unused sections are not placed
+    first: Option<(SourceLine<'a>, Span)>,
+    /// Statements and the index of their line
+    program: Vec<(usize, SourceLine<'a>, Statement)>,
+}
+
+impl<'a> SectionState<'a> {
+    fn new(name: &str) -> Self {
+        SectionState {
+            name: name.to_string(),
+            bss: name == ".bss",
+            origin: None,
+            size: 0,
+            alignment: 1,
+            first: None,
+            program: Vec::new(),
+        }
+    }
+
+    /// Address of the current offset, as far as it is known in the first pass.
+    fn address(&self) -> Word {
+        self.origin.as_ref().map_or(0, |origin| origin.address) + self.size
+    }
+
+    /// Whether `size` more bytes fit into the address space after the current offset,
+    /// reports the line otherwise.
+    fn fits(&self, size: u64, line: &SourceLine, diagnostics: &mut Vec<Diagnostic>) -> bool {
+        if self.address() as u64 + size <= Word::MAX as u64 {
+            return true;
+        }
+
+        diagnostics.push(line.error(
+            first_word(line.text).1,
+            format!("section `{}` does not fit in the address space", self.name),
+        ));
+        false
+    }
+}
+
+pub fn gcd(a: Word, b: Word) -> Word {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Evaluates the directives that decide the following addresses. Fill, alignment, origin
+/// and binaries become plain bytes, `None` if nothing is emitted.
+fn first_pass_data<'a>(
+    data: Data,
+    section: &mut SectionState<'a>,
+    index: usize,
+    line: &SourceLine<'a>,
+    options: &Options,
+    symbols: &mut SymbolTable<'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Data> {
+    let data = match data {
+        Data::Fill(count, value) => {
+            let value = resolve(&value, 1, "fill", line, symbols, diagnostics);
+            let span = count.span;
+            let count = resolve(&count, 4, "count", line, symbols, diagnostics);
+            if (count as i32) < 0 {
+                diagnostics.push(line.error(span, "fill count must not be negative".to_string()));
+                return None;
+            } else if section.bss && value != 0 {
+                diagnostics.push(line.error(
+                    first_word(line.text).1,
+                    format!("`{}` can only be filled with zeros", section.name),
+                ));
+                return None;
+            } else if !section.fits(count as u64, line, diagnostics) {
+                return None;
+            }
+            Data::Bytes(vec![value as Byte; count as usize])
+        }
+        Data::Align(operand) => {
+            let alignment = resolve(&operand, 4, "alignment", line, symbols, diagnostics);
+            if alignment == 0 {
+                diagnostics
+                    .push(line.error(operand.span, "alignment must be greater than 0".to_string()));
+                return None;
+            }
+
+            section.alignment = match (section.alignment / gcd(section.alignment, alignment))
+                .checked_mul(alignment)
+            {
+                Some(alignment) => alignment,
+                None => {
+                    diagnostics.push(line.error(
+                        operand.span,
+                        format!(
+                            "`{}` can't be aligned to 0x{:X} and to the alignments before",
+                            section.name, alignment
+                        ),
+                    ));
+                    return None;
+                }
+            };
+            let padding = (alignment - section.address() % alignment) % alignment;
+            if !section.fits(padding as u64, line, diagnostics) {
+                return None;
+            }
+            Data::Bytes(vec![0; padding as usize])
+        }
+        Data::Org(operand) => {
+            let target = resolve(&operand, 4, "origin", line, symbols, diagnostics);
+
+            // a leading .org places the section, a later one pads up to the address
+            if section.size == 0 && section.origin.is_none() {
+                section.origin = Some(Origin {
+                    address: target,
+                    line: *line,
+                    span: operand.span,
+                    layout: false,
+                });
+                symbols.place(index, target);
+                return None;
+            } else if section.origin.is_none() {
+                diagnostics.push(line.error(
+                    operand.span,
+                    format!(
+                        ".org after the start of `{}` needs the section to start with .org",
+                        section.name
+                    ),
+                ));
+                return None;
+            } else if target < section.address() {
+                diagnostics.push(line.error(
+                    operand.span,
+                    format!(
+                        ".org 0x{:X} is before the current address 0x{:X}",
+                        target,
+                        section.address()
+                    ),
+                ));
+                return None;
+            } else if !section.fits((target - section.address()) as u64, line, diagnostics) {
+                return None;
+            }
+            Data::Bytes(vec![0; (target - section.address()) as usize])
+        }
+        Data::Binary(name, span) => {
+            let bytes = find_file(line.file, &name, &options.include_paths)
+                .ok_or_else(|| {
+                    format!(
+                        "can't find `{}` next to {} or in the include paths",
+                        name, line.file
+                    )
+                })
+                .and_then(|path| {
+                    fs::read(&path).map_err(|e| format!("can't read `{}`: {}", path.display(), e))
+                });
+            match bytes {
+                Ok(bytes) => Data::Bytes(bytes),
+                Err(message) => {
+                    diagnostics.push(line.error(span, message));
+                    return None;
+                }
+            }
+        }
+        data => data,
+    };
+
+    // zeros from .fill, .space, .align and .org are the only data in bss sections
+    let zeros = matches!(&data, Data::Bytes(bytes) if bytes.iter().all(|b| *b == 0));
+    if section.bss && !zeros {
+        diagnostics.push(line.error(
+            first_word(line.text).1,
+            format!(
+                "`{}` only reserves space, use .space, .fill or .align",
+                section.name
+            ),
+        ));
+        return None;
+    }
+
+    Some(data)
+}
+
+/// Decides the order of the sections, from the layout file or by default `.text`, `.data`,
+/// other sections in order of appearance and `.bss` last.
+fn order<'a>(
+    sections: &mut [SectionState<'a>],
+    options: &'a Options,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<usize> {
+    let layout = match &options.layout {
+        Some(layout) => layout,
+        None => {
+            let mut order: Vec<usize> = (0..sections.len()).collect();
+            order.sort_by_key(|&i| default_rank(&sections[i].name, sections[i].bss));
+            return order;
+        }
+    };
+
+    let (placements, errors) = layout.parse();
+    diagnostics.extend(errors);
+
+    let mut order = Vec::new();
+    for placement in placements {
+        let index = match sections.iter().position(|s| s.name == placement.name) {
+            Some(index) => index,
+            // sections of other programs sharing the layout
+            None => continue,
+        };
+        order.push(index);
+
+        let section = &mut sections[index];
+        match (&section.origin, placement.address) {
+            (Some(origin), Some(address)) if origin.address != address => {
+                diagnostics.push(
+                    placement
+                        .line
+                        .error(
+                            placement.span,
+                            format!(
+                                "section `{}` is placed at 0x{:X}, but starts with .org 0x{:X}",
+                                section.name, address, origin.address
+                            ),
+                        )
+                        .with_note(origin.line.note(origin.span, ".org is here".to_string())),
+                );
+            }
+            (None, Some(address)) => {
+                section.origin = Some(Origin {
+                    address,
+                    line: placement.line,
+                    span: placement.span,
+                    layout: true,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    for (index, section) in sections.iter().enumerate() {
+        if let (false, Some((line, span))) = (order.contains(&index), &section.first) {
+            diagnostics.push(line.error(
+                *span,
+                format!(
+                    "section `{}` is missing from the layout file {}",
+                    section.name, layout.file_name
+                ),
+            ));
+        }
+    }
+
+    order
+}
+
+/// A `.struct` being defined, its fields are placed one after another.
+struct Structure<'a> {
+    name: String,
+    line: SourceLine<'a>,
+    span: Span,
+    /// Offset of the next field
+    size: Word,
+}
+
+/// Defines the fields of a `.struct` as constants, `Name.field` is the offset of the field
+/// and `Name.size` the size of the whole struct. Returns the statements outside of structs.
+fn define_struct<'a>(
+    statement: Statement,
+    structure: &mut Option<Structure<'a>>,
+    line: SourceLine<'a>,
+    symbols: &mut SymbolTable<'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Statement> {
+    // the constant the statement defines, if any
+    let constant = match (statement, structure.as_mut()) {
+        (Statement::Struct(name, span), None) => {
+            *structure = Some(Structure {
+                name,
+                line,
+                span,
+                size: 0,
+            });
+            None
+        }
+        (Statement::Field(name, span, size), Some(open)) => {
+            let offset = open.size;
+            let size = resolve(&size, 4, "size", &line, symbols, diagnostics);
+            open.size = open.size.wrapping_add(size);
+            Some((format!("{}.{}", open.name, name), offset, line, span))
+        }
+        (Statement::EndStruct, Some(_)) => {
+            let open = structure.take().unwrap();
+            Some((
+                format!("{}.size", open.name),
+                open.size,
+                open.line,
+                open.span,
+            ))
+        }
+        (Statement::Field(..), None) | (Statement::EndStruct, None) => {
+            let (directive, span, _) = first_word(line.text);
+            diagnostics.push(line.error(span, format!("`{}` without `.struct`", directive)));
+            None
+        }
+        (statement, None) => return Some(statement),
+        (_, Some(open)) => {
+            diagnostics.push(line.error(
+                first_word(line.text).1,
+                format!(
+                    "only `.field` can be used inside of `.struct {}`",
+                    open.name
+                ),
+            ));
+            None
+        }
+    };
+
+    if let Some((name, offset, line, span)) = constant {
+        let value = Value::Constant(Expr::Number(offset), span);
+        if let Err(diagnostic) = symbols.define(&name, value, line, span) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    None
+}
+
+/// Parses every line and assigns every label an offset inside its section. Also returns
+/// the section and offset of every line, for listings.
+fn first_pass<'a>(
+    lines: &'a [Line],
+    procedures: &[Procedure],
+    options: &Options,
+    symbols: &mut SymbolTable<'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (Vec<SectionState<'a>>, Vec<(usize, Word)>) {
+    let mut sections = vec![SectionState::new(".text")];
+    let mut current = 0;
+    // exported once all symbols are defined
+    let mut globals = Vec::new();
+    let mut scope = Scope::new(procedures);
+    let mut structure = None;
+    let mut positions = Vec::with_capacity(lines.len());
+
+    for (index, line) in lines.iter().enumerate() {
+        positions.push((current, sections[current].size));
+        if line.listed_only {
+            continue;
+        }
+
+        let procedure = line.procedure.as_deref();
+        let line = line.source();
+        let mut statement = match parse_line(&line, diagnostics) {
+            Some(statement) => statement,
+            None => continue,
+        };
+        scope.qualify(&mut statement, procedure);
+        let statement = match define_struct(statement, &mut structure, line, symbols, diagnostics) {
+            Some(statement) => statement,
+            None => continue,
+        };
+
+        if let Statement::Section(name, span) = &statement {
+            symbols.close(sections[current].size);
+            current = match sections.iter().position(|s| s.name == *name) {
+                Some(index) => index,
+                None => {
+                    sections.push(SectionState::new(name));
+                    sections.len() - 1
+                }
+            };
+            sections[current].first.get_or_insert((line, *span));
+            positions[index] = (current, sections[current].size);
+            continue;
+        }
+
+        let section = &mut sections[current];
+        section.first.get_or_insert((line, first_word(line.text).1));
+
+        match statement {
+            Statement::Label(label, span) => {
+                let address = Value::Address(current, section.size);
+                if let Err(diagnostic) = symbols.define(&label, address, line, span) {
+                    diagnostics.push(diagnostic);
+                }
+            }
+            Statement::Instruction(_) if section.bss => {
+                diagnostics.push(line.error(
+                    first_word(line.text).1,
+                    format!(
+                        "`{}` only reserves space, instructions belong into `.text`",
+                        section.name
+                    ),
+                ));
+            }
+            Statement::Instruction(instruction) => {
+                if !section.fits(instruction.size() as u64, &line, diagnostics) {
+                    continue;
+                }
+                section.size += instruction.size();
+                section
+                    .program
+                    .push((index, line, Statement::Instruction(instruction)));
+            }
+            Statement::Constant(name, span, value) => {
+                let value = match value.kind {
+                    OperandKind::Value(expr) => Value::Constant(expr, value.span),
+                    // registers were already reported by the parser
+                    OperandKind::Register(_) => continue,
+                };
+                if let Err(diagnostic) = symbols.define(&name, value, line, span) {
+                    diagnostics.push(diagnostic);
+                }
+            }
+            Statement::Data(data) => {
+                let data = match first_pass_data(
+                    data,
+                    section,
+                    current,
+                    &line,
+                    options,
+                    symbols,
+                    diagnostics,
+                ) {
+                    Some(data) => data,
+                    None => continue,
+                };
+
+                let size = match &data {
+                    Data::Values(width, values) => (width * values.len()) as u64,
+                    Data::Bytes(bytes) => bytes.len() as u64,
+                    _ => 0,
+                };
+                if !section.fits(size, &line, diagnostics) {
+                    continue;
+                }
+                section.size += size as Word;
+                section.program.push((index, line, Statement::Data(data)));
+            }
+            Statement::Extern(names) => {
+                for (name, span) in names {
+                    if let Err(diagnostic) = symbols.define(&name, Value::Extern, line, span) {
+                        diagnostics.push(diagnostic);
+                    }
+                }
+            }
+            Statement::Global(names) => {
+                globals.extend(names.into_iter().map(|(name, span)| (line, name, span)));
+            }
+            Statement::Section(..)
+            | Statement::Struct(..)
+            | Statement::Field(..)
+            | Statement::EndStruct => {}
+        }
+    }
+    symbols.close(sections[current].size);
+
+    if let Some(open) = structure {
+        diagnostics.push(open.line.error(
+            open.span,
+            format!("`.struct {}` without matching `.endstruct`", open.name),
+        ));
+    }
+
+    for (line, name, span) in globals {
+        if let Err(diagnostic) = symbols.export(&name, &line, span) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    (sections, positions)
+}
+
+/// Assembles the source in two passes after expanding macros.
+///
+/// The first pass parses every line and assigns every label an offset inside its section,
+/// then the sections are placed one after another. The second pass encodes the
+/// instructions with all labels known. The image is built in memory, so nothing has to
+/// be patched afterwards.
+pub fn assemble(source: &str, options: &Options) -> Output {
+    let (lines, procedures, mut diagnostics) = preprocess(source, options);
+
+    let mut symbols = SymbolTable::default();
+    let (mut sections, positions) =
+        first_pass(&lines, &procedures, options, &mut symbols, &mut diagnostics);
+
+    // layout, unused sections like an empty .text are not placed
+    let order: Vec<usize> = order(&mut sections, options, &mut diagnostics)
+        .into_iter()
+        .filter(|&i| sections[i].first.is_some())
+        .collect();
+
+    // the first section decides the base if none was given
+    let base = options.base.unwrap_or_else(|| {
+        order
+            .first()
+            .and_then(|&i| sections[i].origin.as_ref())
+            .map_or(0, |origin| origin.address)
+    });
+
+    let mut address = base;
+    let mut placed: Vec<Section> = Vec::new();
+    let mut starts = vec![base; sections.len()];
+    for &index in &order {
+        let section = &sections[index];
+        let start = match &section.origin {
+            Some(origin) if origin.address < address => {
+                let message = if origin.layout {
+                    format!(
+                        "section `{}` at 0x{:X} is before the current address 0x{:X}",
+                        section.name, origin.address, address
+                    )
+                } else {
+                    format!(
+                        ".org 0x{:X} is before the current address 0x{:X}",
+                        origin.address, address
+                    )
+                };
+                diagnostics.push(origin.line.error(origin.span, message));
+                address
+            }
+            Some(origin) => origin.address,
+            None => match address % section.alignment {
+                0 => address,
+                // past the end of the address space, reported below
+                rest => address
+                    .checked_add(section.alignment - rest)
+                    .unwrap_or(Word::MAX),
+            },
+        };
+        let end = match start.checked_add(section.size) {
+            Some(end) => end,
+            None => {
+                if let Some((line, span)) = &section.first {
+                    diagnostics.push(line.error(
+                        *span,
+                        format!(
+                            "section `{}` of {} byte(s) at 0x{:X} does not fit in the address space",
+                            section.name, section.size, start
+                        ),
+                    ));
+                }
+                Word::MAX
+            }
+        };
+
+        symbols.place(index, start);
+        starts[index] = start;
+        placed.push(Section {
+            name: section.name.clone(),
+            address: start,
+            size: end - start,
+            bss: section.bss,
+        });
+        address = end;
+    }
+
+    // bss sections after the last initialized byte are not part of the image
+    let end = placed
+        .iter()
+        .filter(|s| !s.bss && s.size > 0)
+        .map(|s| s.address.saturating_add(s.size))
+        .max()
+        .unwrap_or(base);
+
+    // second pass
+    let mut encoded = Encoded {
+        image: Vec::with_capacity(end.saturating_sub(base) as usize),
+        ..Encoded::default()
+    };
+    // bytes of every line inside the image
+    let mut ranges = vec![0..0; lines.len()];
+    for (&index, section) in order.iter().zip(&placed) {
+        if section.address >= end {
+            break;
+        }
+        encoded.image.resize((section.address - base) as usize, 0);
+
+        for (line_index, line, statement) in &sections[index].program {
+            let start = encoded.image.len();
+            encode(
+                statement,
+                line,
+                &mut symbols,
+                &mut diagnostics,
+                &mut encoded,
+            );
+            if !section.bss {
+                ranges[*line_index] = start..encoded.image.len();
+            }
+        }
+    }
+
+    if let Some(entry) = &options.entry {
+        symbols.mark_used(entry);
+    }
+    diagnostics.extend(symbols.unused());
+
+    let values = symbols.values();
+    let entry = entry_point(&values, options, base, &options.file_name, &mut diagnostics);
+    let mut locals: Vec<(Word, usize)> = procedures
+        .iter()
+        .filter_map(|procedure| {
+            let symbol = values
+                .iter()
+                .find(|s| !s.constant && s.name == procedure.name)?;
+            Some((symbol.value, procedure.locals))
+        })
+        .collect();
+    locals.sort();
+
+    let listing = lines
+        .iter()
+        .zip(positions)
+        .zip(ranges)
+        .map(|((line, (section, offset)), range)| ListingLine {
+            file: line.file.clone(),
+            line: line.line,
+            text: line.text.clone(),
+            expanded: line.expanded,
+            address: starts[section].wrapping_add(offset),
+            bytes: encoded.image[range].to_vec(),
+        })
+        .collect();
+
+    Output {
+        image: encoded.image,
+        base,
+        entry,
+        sections: placed,
+        listing,
+        symbols: values,
+        locals,
+        diagnostics,
+    }
+}
+
+/// Address of the `--entry` label, the base without one. `file` is named in the error.
+pub fn entry_point(
+    symbols: &[DefinedSymbol],
+    options: &Options,
+    base: Word,
+    file: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Word {
+    let name = match &options.entry {
+        Some(name) => name,
+        None => return base,
+    };
+
+    match symbols.iter().find(|symbol| symbol.name == *name) {
+        Some(symbol) => symbol.value,
+        None => {
+            diagnostics.push(Diagnostic::file_error(
+                file,
+                format!("entry point `{}` is not defined", name),
+            ));
+            base
+        }
+    }
+}
+
+/// Assembles the source into a relocatable object for the linker.
+///
+/// The first pass is the same as for a binary, but the sections are not placed. Labels
+/// stay offsets inside their section and every value that depends on them or on an
+/// external symbol becomes a relocation. Sections starting with `.org` keep their address.
+pub fn assemble_object(source: &str, options: &Options) -> ObjectOutput {
+    let (lines, procedures, mut diagnostics) = preprocess(source, options);
+
+    let mut symbols = SymbolTable::default();
+    symbols.relocatable = true;
+    let (sections, _) = first_pass(&lines, &procedures, options, &mut symbols, &mut diagnostics);
+
+    // unused sections like an empty .text are left out, labels are only in used ones
+    let used: Vec<usize> = (0..sections.len())
+        .filter(|&i| sections[i].first.is_some())
+        .collect();
+    let index = |section: usize| used.iter().position(|&i| i == section).unwrap_or(0);
+
+    let mut object = Object::default();
+    for &i in &used {
+        let section = &sections[i];
+        let mut encoded = Encoded {
+            section: i,
+            ..Encoded::default()
+        };
+        for (_, line, statement) in &section.program {
+            encode(
+                statement,
+                line,
+                &mut symbols,
+                &mut diagnostics,
+                &mut encoded,
+            );
+        }
+
+        object.sections.push(ObjectSection {
+            name: section.name.clone(),
+            bss: section.bss,
+            alignment: section.alignment,
+            origin: section.origin.as_ref().map(|origin| origin.address),
+            size: section.size,
+            data: if section.bss {
+                Vec::new()
+            } else {
+                encoded.image
+            },
+        });
+        object.relocations.extend(
+            encoded
+                .relocations
+                .into_iter()
+                .map(|relocation| Relocation {
+                    section: index(relocation.section),
+                    target: match relocation.target {
+                        Target::Section(section) => Target::Section(index(section)),
+                        target => target,
+                    },
+                    ..relocation
+                }),
+        );
+    }
+
+    let mut defined: Vec<_> = symbols.iter().collect();
+    defined.sort_by_key(|(_, s)| (s.line.file, s.line.line, s.span.start));
+
+    // constants are replaced by their value, only exported ones are kept
+    let mut constants = Vec::new();
+    for (name, symbol) in defined {
+        let value = match &symbol.value {
+            Value::Address(section, offset) => SymbolValue::Address(index(*section), *offset),
+            Value::Extern => SymbolValue::Extern,
+            Value::Constant(expr, span) if symbol.global => {
+                constants.push((name.clone(), expr.clone(), symbol.line, *span));
+                continue;
+            }
+            Value::Constant(..) => continue,
+        };
+        object.symbols.push(ObjectSymbol {
+            name: name.clone(),
+            value,
+            global: symbol.global,
+            location: location(&symbol.line),
+        });
+    }
+
+    for (name, expr, line, span) in constants {
+        let value = match symbols.evaluate_relocatable(&expr, &line, span) {
+            Ok(Relocatable { value, base: None }) => SymbolValue::Absolute(value),
+            Ok(Relocatable {
+                value,
+                base: Some(Base::Section(section)),
+            }) => SymbolValue::Address(index(section), value),
+            Ok(_) => {
+                diagnostics.push(line.error(
+                    span,
+                    format!(
+                        "`{}` depends on an external symbol, it can't be .global",
+                        name
+                    ),
+                ));
+                continue;
+            }
+            Err(diagnostic) => {
+                diagnostics.push(diagnostic);
+                continue;
+            }
+        };
+        object.symbols.push(ObjectSymbol {
+            name,
+            value,
+            global: true,
+            location: location(&line),
+        });
+    }
+
+    diagnostics.extend(symbols.unused());
+
+    ObjectOutput {
+        object,
+        diagnostics,
+    }
+}
+
+/// `file:line` of a source line, used by the linker to locate symbols and relocations.
+fn location(line: &SourceLine) -> String {
+    format!("{}:{}", line.file, line.line)
+}
+
+/// Output of the second pass.
+#[derive(Default)]
+struct Encoded {
+    image: Vec<Byte>,
+    /// Section being encoded, object files get an image per section
+    section: usize,
+    /// Values the linker fills in, only in object files
+    relocations: Vec<Relocation>,
+}
+
+impl Encoded {
+    /// Returns the value to write at `offset` of the image. Values relative to a base become
+    /// relocations and are written as 0, the linker adds the base.
+    fn relocate(
+        &mut self,
+        value: Relocatable,
+        offset: usize,
+        width: usize,
+        line: &SourceLine,
+    ) -> Word {
+        let target = match value.base {
+            None => return value.value,
+            Some(Base::Section(section)) => Target::Section(section),
+            Some(Base::Extern(name)) => Target::Symbol(name),
+        };
+
+        self.relocations.push(Relocation {
+            section: self.section,
+            offset: offset as Word,
+            width: width as Byte,
+            target,
+            addend: value.value,
+            location: location(line),
+        });
+        0
+    }
+}
+
+/// Encodes a statement of the first pass into the image.
+fn encode(
+    statement: &Statement,
+    line: &SourceLine,
+    symbols: &mut SymbolTable,
+    diagnostics: &mut Vec<Diagnostic>,
+    encoded: &mut Encoded,
+) {
+    match statement {
+        Statement::Instruction(instruction) => {
+            // operands follow the opcode
+            let mut offset = encoded.image.len() + 1;
+            let values: Vec<Word> = instruction
+                .operands
+                .iter()
+                .zip(instruction.definition.operands)
+                .map(|(operand, kind)| {
+                    let width = kind.width();
+                    offset += width;
+
+                    if *kind == isa::OperandKind::Flag {
+                        let value = resolve(operand, width, kind.name(), line, symbols, diagnostics);
+                        if value >= 32 {
+                            diagnostics.push(line.error(
+                                operand.span,
+                                format!(
+                                    "status bit `{}` of {} is out of range, the status register has 32 bits",
+                                    &line.text[operand.span.start..operand.span.end],
+                                    instruction.definition.mnemonic
+                                ),
+                            ));
+                        }
+                        return value;
+                    }
+
+                    let value = resolve_relocatable(operand, width, kind.name(), line, symbols, diagnostics);
+                    encoded.relocate(value, offset - width, width, line)
+                })
+                .collect();
+
+            instruction.definition.encode(&values, &mut encoded.image);
+        }
+        Statement::Data(Data::Values(width, values)) => {
+            for operand in values {
+                let value =
+                    resolve_relocatable(operand, *width, "data", line, symbols, diagnostics);
+                let value = encoded.relocate(value, encoded.image.len(), *width, line);
+                encoded
+                    .image
+                    .extend_from_slice(&value.to_le_bytes()[..*width]);
+            }
+        }
+        Statement::Data(Data::Bytes(bytes)) => encoded.image.extend_from_slice(bytes),
+        // fill, alignment, origin and binaries became bytes in the first pass, labels and constants are in the symbol table
+        _ => {}
+    }
+}
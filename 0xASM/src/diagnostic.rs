@@ -0,0 +1,146 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+/// Byte columns `start..end` of the offending text inside its source line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    pub span: Span,
+    /// Text of the source line, used to draw the caret
+    pub source: String,
+
+    /// Additional locations related to this diagnostic
+    pub notes: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// An error about a whole file, like an object file passed to the linker.
+    pub fn file_error(file: &str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            file: file.to_string(),
+            line: 0,
+            span: Span::new(0, 0),
+            source: String::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_note(mut self, note: Diagnostic) -> Self {
+        self.notes.push(note);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+        writeln!(f, "{}: {}", severity, self.message)?;
+
+        // there is no line to point at in errors about a whole file
+        if self.line == 0 {
+            write!(f, " --> {}", self.file)?;
+            for note in &self.notes {
+                write!(f, "\n{}", note)?;
+            }
+            return Ok(());
+        }
+
+        let line_no = self.line.to_string();
+        let gutter = " ".repeat(line_no.len());
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter,
+            self.file,
+            self.line,
+            self.span.start + 1
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line_no, self.source)?;
+
+        // keep tabs so the caret lines up with the source line above
+        let start = self.span.start.min(self.source.len());
+        let padding: String = self.source[..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = self.span.end.saturating_sub(self.span.start).max(1);
+        write!(f, "{} | {}{}", gutter, padding, "^".repeat(width))?;
+
+        for note in &self.notes {
+            write!(f, "\n{}", note)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single line of source, used to create diagnostics pointing into it.
+#[derive(Clone, Copy)]
+pub struct SourceLine<'a> {
+    pub file: &'a str,
+    /// 1-based line number
+    pub line: usize,
+    pub text: &'a str,
+    /// Notes on the macro invocations the line was expanded from
+    pub context: &'a [Diagnostic],
+}
+
+impl<'a> SourceLine<'a> {
+    pub fn diagnostic(&self, severity: Severity, span: Span, message: String) -> Diagnostic {
+        Diagnostic {
+            severity,
+            message,
+            file: self.file.to_string(),
+            line: self.line,
+            span,
+            source: self.text.to_string(),
+            notes: self.context.to_vec(),
+        }
+    }
+
+    pub fn error(&self, span: Span, message: String) -> Diagnostic {
+        self.diagnostic(Severity::Error, span, message)
+    }
+
+    pub fn warning(&self, span: Span, message: String) -> Diagnostic {
+        self.diagnostic(Severity::Warning, span, message)
+    }
+
+    pub fn note(&self, span: Span, message: String) -> Diagnostic {
+        self.diagnostic(Severity::Note, span, message)
+    }
+}
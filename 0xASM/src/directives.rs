@@ -0,0 +1,252 @@
+use crate::expression::{is_identifier, is_identifier_start, Expr};
+use crate::parser::{
+    parse_operand, parse_quoted, trim_with_offset, Operand, OperandKind, Statement,
+};
+use crate::preprocessor::parse_file_name;
+use crate::{Byte, Diagnostic, SourceLine, Span};
+
+pub enum Data {
+    /// Values with the given width in bytes
+    Values(usize, Vec<Operand>),
+    Bytes(Vec<Byte>),
+    /// `count` times the byte `value`, evaluated in the first pass
+    Fill(Operand, Operand),
+    /// Zero padding up to the next multiple of the alignment, evaluated in the first pass
+    Align(Operand),
+    /// Zero padding up to an absolute address, evaluated in the first pass
+    Org(Operand),
+    /// Contents of a file, read in the first pass
+    Binary(String, Span),
+}
+
+fn parse_values(
+    line: &SourceLine,
+    operands: Vec<(&str, Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Operand> {
+    operands
+        .into_iter()
+        .map(|(operand, span)| {
+            let kind = match parse_operand(operand) {
+                Ok(OperandKind::Register(_)) => Err(format!(
+                    "registers can't be used as data, got `{}`",
+                    operand
+                )),
+                other => other,
+            }
+            .unwrap_or_else(|message| {
+                diagnostics.push(line.error(span, message));
+                OperandKind::Value(Expr::Number(0))
+            });
+            Operand { kind, span }
+        })
+        .collect()
+}
+
+fn parse_strings(
+    line: &SourceLine,
+    operands: Vec<(&str, Span)>,
+    terminate: bool,
+) -> Result<Vec<Byte>, Diagnostic> {
+    let mut bytes = Vec::new();
+    for (operand, span) in operands {
+        if !operand.starts_with('"') {
+            return Err(line.error(span, format!("expected string literal, got `{}`", operand)));
+        }
+
+        bytes.extend(parse_quoted(operand, '"').map_err(|message| line.error(span, message))?);
+        if terminate {
+            bytes.push(0);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Whether `name` can be used for a struct or field, the names of its constants start with it.
+fn is_symbol_name(name: &str) -> bool {
+    name.starts_with(is_identifier_start)
+        && !name.starts_with('.')
+        && name.chars().all(is_identifier)
+        && isa::register_offset(name).is_none()
+}
+
+/// Splits the name and value of `.equ NAME, value` and `.define NAME value`, the comma is optional.
+pub fn constant_operands(operands: Vec<(&str, Span)>) -> Vec<(&str, Span)> {
+    if operands.len() == 1 {
+        let (operand, span) = operands[0];
+        if let Some(i) = operand.find(char::is_whitespace) {
+            let (column, value) = trim_with_offset(&operand[i..], span.start + i);
+            return vec![
+                (&operand[..i], Span::new(span.start, span.start + i)),
+                (value, Span::new(column, column + value.len())),
+            ];
+        }
+    }
+
+    operands
+}
+
+/// Parses `.equ NAME, value` and `.define NAME value`.
+fn parse_constant(
+    line: &SourceLine,
+    operands: Vec<(&str, Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Statement, Diagnostic> {
+    let operands = constant_operands(operands);
+    let (name, span) = operands[0];
+    if !name.starts_with(is_identifier_start)
+        || !name.chars().all(is_identifier)
+        || isa::register_offset(name).is_some()
+    {
+        return Err(line.error(span, format!("invalid constant name `{}`", name)));
+    }
+
+    match parse_values(line, operands[1..].to_vec(), diagnostics).pop() {
+        Some(value) => Ok(Statement::Constant(name.to_string(), span, value)),
+        None => Err(line.error(span, format!("constant `{}` has no value", name))),
+    }
+}
+
+/// Parses directives like `.byte 0x1, 0x2`, `.asciz "text"` or `.equ SIZE, 4`.
+pub fn parse_directive(
+    line: &SourceLine,
+    name: &str,
+    name_span: Span,
+    operands: Vec<(&str, Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Statement> {
+    let directive = name.to_lowercase();
+
+    let expect_operands = |min: usize, max: usize| {
+        if operands.len() < min || operands.len() > max || (min > 0 && operands[0].0.is_empty()) {
+            let count = if min == max {
+                min.to_string()
+            } else if max == usize::MAX {
+                format!("at least {}", min)
+            } else {
+                format!("{} to {}", min, max)
+            };
+            Err(line.error(
+                name_span,
+                format!(
+                    "{} expects {} operand(s), got {}",
+                    directive,
+                    count,
+                    operands.len()
+                ),
+            ))
+        } else {
+            Ok(())
+        }
+    };
+
+    let value =
+        |operands: &[(&str, Span)], index: usize, diagnostics: &mut Vec<Diagnostic>| match operands
+            .get(index)
+        {
+            Some(&operand) => parse_values(line, vec![operand], diagnostics).remove(0),
+            None => Operand {
+                kind: OperandKind::Value(Expr::Number(0)),
+                span: name_span,
+            },
+        };
+
+    let statement = match directive.as_str() {
+        ".byte" | ".half" | ".word" => expect_operands(1, usize::MAX).map(|_| {
+            let width = match directive.as_str() {
+                ".byte" => 1,
+                ".half" => 2,
+                _ => 4,
+            };
+            Statement::Data(Data::Values(
+                width,
+                parse_values(line, operands, diagnostics),
+            ))
+        }),
+        ".ascii" | ".asciz" => expect_operands(1, usize::MAX)
+            .and_then(|_| parse_strings(line, operands, directive == ".asciz"))
+            .map(|bytes| Statement::Data(Data::Bytes(bytes))),
+        ".fill" => expect_operands(1, 2).map(|_| {
+            let count = value(&operands, 0, diagnostics);
+            Statement::Data(Data::Fill(count, value(&operands, 1, diagnostics)))
+        }),
+        ".align" => expect_operands(1, 1)
+            .map(|_| Statement::Data(Data::Align(value(&operands, 0, diagnostics)))),
+        ".org" => expect_operands(1, 1)
+            .map(|_| Statement::Data(Data::Org(value(&operands, 0, diagnostics)))),
+        ".space" => expect_operands(1, 1).map(|_| {
+            let zero = Operand {
+                kind: OperandKind::Value(Expr::Number(0)),
+                span: name_span,
+            };
+            Statement::Data(Data::Fill(value(&operands, 0, diagnostics), zero))
+        }),
+        ".text" | ".data" | ".bss" => {
+            expect_operands(0, 0).map(|_| Statement::Section(directive.clone(), name_span))
+        }
+        ".section" => expect_operands(1, 1).and_then(|_| {
+            let (section, span) = operands[0];
+            if section.starts_with(is_identifier_start) && section.chars().all(is_identifier) {
+                Ok(Statement::Section(section.to_string(), span))
+            } else {
+                Err(line.error(span, format!("invalid section name `{}`", section)))
+            }
+        }),
+        ".global" | ".extern" => expect_operands(1, usize::MAX).and_then(|_| {
+            let mut names = Vec::new();
+            for (name, span) in operands {
+                if !name.starts_with(is_identifier_start) || !name.chars().all(is_identifier) {
+                    return Err(line.error(span, format!("invalid symbol name `{}`", name)));
+                }
+                names.push((name.to_string(), span));
+            }
+
+            Ok(match directive.as_str() {
+                ".global" => Statement::Global(names),
+                _ => Statement::Extern(names),
+            })
+        }),
+        ".struct" => expect_operands(1, 1).and_then(|_| {
+            let (name, span) = operands[0];
+            match is_symbol_name(name) {
+                true => Ok(Statement::Struct(name.to_string(), span)),
+                false => Err(line.error(span, format!("invalid struct name `{}`", name))),
+            }
+        }),
+        ".field" => expect_operands(1, 2).and_then(|_| {
+            let (name, span) = operands[0];
+            if !is_symbol_name(name) {
+                return Err(line.error(span, format!("invalid field name `{}`", name)));
+            }
+
+            // fields are a word unless a size is given
+            let size = match operands.len() {
+                1 => Operand {
+                    kind: OperandKind::Value(Expr::Number(4)),
+                    span,
+                },
+                _ => value(&operands, 1, diagnostics),
+            };
+            Ok(Statement::Field(name.to_string(), span, size))
+        }),
+        ".endstruct" => expect_operands(0, 0).map(|_| Statement::EndStruct),
+        ".incbin" => expect_operands(1, 1).and_then(|_| {
+            let (operand, span) = operands[0];
+            let name = parse_file_name(line, operand, span)?;
+            Ok(Statement::Data(Data::Binary(name, span)))
+        }),
+        ".equ" | ".define" => {
+            expect_operands(1, 2).and_then(|_| parse_constant(line, operands, diagnostics))
+        }
+        _ => Err(line.error(name_span, format!("unknown directive `{}`", name))),
+    };
+
+    match statement {
+        Ok(statement) => Some(statement),
+        Err(diagnostic) => {
+            diagnostics.push(diagnostic);
+            None
+        }
+    }
+}
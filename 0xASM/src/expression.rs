@@ -0,0 +1,318 @@
+use std::convert::TryInto;
+
+use crate::parser::parse_quoted;
+use crate::Word;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+    /// `!`, 1 if the operand is 0, otherwise 0
+    LogicalNot,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Shl,
+    Shr,
+    And,
+    Or,
+    Xor,
+    // comparisons and logical operators result in 1 or 0
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    LogicalAnd,
+    LogicalOr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Number(Word),
+    /// `:name`, always a label
+    Label(String),
+    /// Bare name of a label or constant
+    Symbol(String),
+    /// `sizeof(name)`, the number of bytes between a label and the next one
+    SizeOf(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// Binary operators from the lowest to the highest precedence, same order as in C.
+const PRECEDENCE: &[&[(&str, BinaryOp)]] = &[
+    &[("||", BinaryOp::LogicalOr)],
+    &[("&&", BinaryOp::LogicalAnd)],
+    &[("|", BinaryOp::Or)],
+    &[("^", BinaryOp::Xor)],
+    &[("&", BinaryOp::And)],
+    &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+    &[
+        ("<=", BinaryOp::Le),
+        (">=", BinaryOp::Ge),
+        ("<", BinaryOp::Lt),
+        (">", BinaryOp::Gt),
+    ],
+    &[("<<", BinaryOp::Shl), (">>", BinaryOp::Shr)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+    &[
+        ("*", BinaryOp::Mul),
+        ("/", BinaryOp::Div),
+        ("%", BinaryOp::Rem),
+    ],
+];
+
+pub fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '.'
+}
+
+pub fn is_identifier(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '@'
+}
+
+/// Whether `name` is an anonymous label like `1`, defined with `:1`.
+pub fn is_anonymous(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Whether `name` references the next or previous anonymous label, like `1f` or `1b`.
+pub fn is_anonymous_reference(name: &str) -> bool {
+    matches!(name.strip_suffix(|c| c == 'f' || c == 'b'), Some(number) if is_anonymous(number))
+}
+
+/// Parses a number literal: hex `0x`, binary `0b`, octal `0o` or decimal, with optional `_` separators.
+pub fn parse_number(literal: &str) -> Result<Word, String> {
+    let digits = literal.replace('_', "");
+    let lower = digits.to_lowercase();
+
+    let (digits, radix, name) = if let Some(digits) = lower.strip_prefix("0x") {
+        (digits, 16, "hex")
+    } else if let Some(digits) = lower.strip_prefix("0b") {
+        (digits, 2, "bin")
+    } else if let Some(digits) = lower.strip_prefix("0o") {
+        (digits, 8, "oct")
+    } else {
+        (lower.as_str(), 10, "dec")
+    };
+
+    Word::from_str_radix(digits, radix)
+        .map_err(|_| format!("invalid {} literal `{}`", name, literal))
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&mut self) -> &'a str {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+        &self.text[self.position..]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Eats a binary operator, unless it's the start of a longer one like `<` of `<<`.
+    fn eat_operator(&mut self, token: &str) -> bool {
+        let rest = self.rest();
+        let longer = PRECEDENCE
+            .iter()
+            .flat_map(|level| level.iter())
+            .any(|(t, _)| t.len() > token.len() && t.starts_with(token) && rest.starts_with(t));
+
+        !longer && self.eat(token)
+    }
+
+    /// Takes chars as long as `f` matches.
+    fn take(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.position += end;
+        &rest[..end]
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, String> {
+        match self.rest().chars().next() {
+            Some(c) if is_identifier_start(c) => Ok(self.take(is_identifier)),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn unexpected(&mut self) -> String {
+        match self.rest().chars().next() {
+            Some(c) => format!("unexpected `{}` in expression `{}`", c, self.text),
+            None => format!("unexpected end of expression `{}`", self.text),
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for (token, op) in PRECEDENCE[level] {
+                if self.eat_operator(token) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'operators;
+                }
+            }
+
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?)))
+        } else if self.eat("~") {
+            Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?)))
+        } else if self.eat("!") {
+            Ok(Expr::Unary(UnaryOp::LogicalNot, Box::new(self.unary()?)))
+        } else if self.eat("+") {
+            self.unary()
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let rest = self.rest();
+
+        if self.eat("(") {
+            let expr = self.binary(0)?;
+            if !self.eat(")") {
+                return Err(self.unexpected());
+            }
+            Ok(expr)
+        } else if rest.starts_with('\'') {
+            self.char_literal()
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            let literal = self.take(|c| c.is_ascii_alphanumeric() || c == '_');
+            match is_anonymous_reference(literal) {
+                true => Ok(Expr::Label(literal.to_string())),
+                false => parse_number(literal).map(Expr::Number),
+            }
+        } else if self.eat(":") {
+            if self.rest().starts_with(|c: char| c.is_ascii_digit()) {
+                let reference = self.take(|c| c.is_ascii_alphanumeric());
+                return match is_anonymous_reference(reference) {
+                    true => Ok(Expr::Label(reference.to_string())),
+                    false => Err(format!(
+                        "expected an anonymous label reference like `1f` or `1b`, got `:{}`",
+                        reference
+                    )),
+                };
+            }
+            Ok(Expr::Label(self.identifier()?.to_string()))
+        } else {
+            let name = self.identifier()?;
+            if name.eq_ignore_ascii_case("sizeof") {
+                if !self.eat("(") {
+                    return Err(self.unexpected());
+                }
+                self.eat(":");
+                let label = self.identifier()?;
+                if !self.eat(")") {
+                    return Err(self.unexpected());
+                }
+                Ok(Expr::SizeOf(label.to_string()))
+            } else if isa::register_offset(name).is_some() {
+                Err(format!(
+                    "registers can't be used in expressions, got `{}`",
+                    name
+                ))
+            } else {
+                Ok(Expr::Symbol(name.to_string()))
+            }
+        }
+    }
+
+    /// Char literals of up to 4 bytes, stored as a little endian word.
+    fn char_literal(&mut self) -> Result<Expr, String> {
+        let rest = self.rest();
+        let mut escaped = false;
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| {
+                let end = !escaped && c == '\'';
+                escaped = !escaped && c == '\\';
+                end
+            })
+            .map(|(i, _)| i + 1)
+            .unwrap_or(rest.len());
+        self.position += end;
+
+        let literal = &rest[..end];
+        let mut bytes = parse_quoted(literal, '\'')?;
+        if bytes.len() > 4 {
+            return Err(format!("char literal `{}` is longer than 4 bytes", literal));
+        }
+        bytes.resize(4, 0);
+        Ok(Expr::Number(Word::from_le_bytes(bytes.try_into().unwrap())))
+    }
+}
+
+/// Parses an expression like `(end - start) / 4` or `'a' | 0x20`.
+pub fn parse_expression(text: &str) -> Result<Expr, String> {
+    let mut parser = Parser { text, position: 0 };
+    let expr = parser.binary(0)?;
+
+    if !parser.rest().is_empty() {
+        return Err(parser.unexpected());
+    }
+
+    Ok(expr)
+}
+
+/// Applies the operator with 32-bit wrapping arithmetic, like the VM does.
+pub fn apply(op: BinaryOp, lhs: Word, rhs: Word) -> Result<Word, String> {
+    Ok(match op {
+        BinaryOp::Add => lhs.wrapping_add(rhs),
+        BinaryOp::Sub => lhs.wrapping_sub(rhs),
+        BinaryOp::Mul => lhs.wrapping_mul(rhs),
+        BinaryOp::Div => lhs.checked_div(rhs).ok_or("division by zero")?,
+        BinaryOp::Rem => lhs.checked_rem(rhs).ok_or("division by zero")?,
+        BinaryOp::Shl => lhs.checked_shl(rhs).unwrap_or(0),
+        BinaryOp::Shr => lhs.checked_shr(rhs).unwrap_or(0),
+        BinaryOp::And => lhs & rhs,
+        BinaryOp::Or => lhs | rhs,
+        BinaryOp::Xor => lhs ^ rhs,
+        BinaryOp::Eq => (lhs == rhs) as Word,
+        BinaryOp::Ne => (lhs != rhs) as Word,
+        // signed, so negative constants compare below 0
+        BinaryOp::Lt => ((lhs as i32) < rhs as i32) as Word,
+        BinaryOp::Le => (lhs as i32 <= rhs as i32) as Word,
+        BinaryOp::Gt => (lhs as i32 > rhs as i32) as Word,
+        BinaryOp::Ge => (lhs as i32 >= rhs as i32) as Word,
+        BinaryOp::LogicalAnd => (lhs != 0 && rhs != 0) as Word,
+        BinaryOp::LogicalOr => (lhs != 0 || rhs != 0) as Word,
+    })
+}
+
+pub fn apply_unary(op: UnaryOp, value: Word) -> Word {
+    match op {
+        UnaryOp::Neg => value.wrapping_neg(),
+        UnaryOp::Not => !value,
+        UnaryOp::LogicalNot => (value == 0) as Word,
+    }
+}
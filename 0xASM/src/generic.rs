@@ -0,0 +1,250 @@
+use crate::expression::{is_identifier, parse_expression, Expr, UnaryOp};
+use crate::parser::{parse_operand, Instruction, Operand, OperandKind, Statement};
+use crate::{Diagnostic, SourceLine, Span, Word};
+
+/// How an operand of a generic instruction is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Form {
+    /// `r1`
+    Register,
+    /// `0x10`, `:label` or any other expression
+    Value,
+    /// `[0x10]`, the word in memory at an address
+    Memory,
+    /// `[r1]`, the word in memory at the address in a register
+    Pointer,
+    /// `[r1 + 4]`, the word in memory at the address in a register plus an offset
+    Offset,
+}
+
+impl Form {
+    fn name(self) -> &'static str {
+        match self {
+            Form::Register => "register",
+            Form::Value => "value",
+            Form::Memory => "[address]",
+            Form::Pointer => "[register]",
+            Form::Offset => "[register + offset]",
+        }
+    }
+}
+
+use Form::{Memory, Offset, Pointer, Register, Value};
+
+/// Generic mnemonic, the operands as written and the instruction they select. Variants
+/// marked with `true` take the operands in reverse order, which is the same for
+/// commutative operations.
+#[rustfmt::skip]
+const VARIANTS: &[(&str, &[Form], &str, bool)] = &[
+    ("MOV", &[Value, Register], "MOVR", false),
+    ("MOV", &[Value, Memory], "MOVM", false),
+    ("MOV", &[Register, Register], "MOVRR", false),
+    ("MOV", &[Register, Memory], "MOVRM", false),
+    ("MOV", &[Memory, Register], "MOVMR", false),
+    ("MOV", &[Pointer, Register], "MOVRPR", false),
+    ("MOV", &[Offset, Register], "MOVROR", false),
+
+    ("LOAD", &[Register, Register, Value], "LOAD", false),
+    ("LOAD", &[Register, Register, Register], "LOADR", false),
+    ("LOAD", &[Register, Register, Memory], "LOADM", false),
+    ("STORE", &[Value, Register, Register], "STORE", false),
+    ("STORE", &[Register, Register, Register], "STORER", false),
+    ("STORE", &[Memory, Register, Register], "STOREM", false),
+
+    ("PUSH", &[Value], "PUSH", false),
+    ("PUSH", &[Register], "PUSHR", false),
+    ("CALL", &[Value], "CALL", false),
+    ("CALL", &[Register], "CALLR", false),
+
+    ("ADD", &[Value, Register], "ADD", false),
+    ("ADD", &[Register, Value], "ADD", true),
+    ("ADD", &[Register, Register], "ADDR", false),
+    ("SUB", &[Register, Value], "SUB", false),
+    ("SUB", &[Value, Register], "SUBWR", false),
+    ("SUB", &[Register, Register], "SUBR", false),
+    ("MULT", &[Value, Register], "MULT", false),
+    ("MULT", &[Register, Value], "MULT", true),
+    ("MULT", &[Register, Register], "MULTR", false),
+    ("DIV", &[Register, Value], "DIV", false),
+    ("DIV", &[Value, Register], "DIVWR", false),
+    ("DIV", &[Register, Register], "DIVR", false),
+
+    ("SHL", &[Register, Value], "LSF", false),
+    ("SHL", &[Register, Register], "LSFR", false),
+    ("SHR", &[Register, Value], "RSF", false),
+    ("SHR", &[Register, Register], "RSFR", false),
+    ("ROL", &[Register, Value], "WLSF", false),
+    ("ROL", &[Register, Register], "WLSFR", false),
+    ("ROR", &[Register, Value], "WRSF", false),
+    ("ROR", &[Register, Register], "WRSFR", false),
+    ("LSF", &[Register, Value], "LSF", false),
+    ("LSF", &[Register, Register], "LSFR", false),
+    ("RSF", &[Register, Value], "RSF", false),
+    ("RSF", &[Register, Register], "RSFR", false),
+    ("WLSF", &[Register, Value], "WLSF", false),
+    ("WLSF", &[Register, Register], "WLSFR", false),
+    ("WRSF", &[Register, Value], "WRSF", false),
+    ("WRSF", &[Register, Register], "WRSFR", false),
+    ("AND", &[Register, Value], "AND", false),
+    ("AND", &[Value, Register], "AND", true),
+    ("AND", &[Register, Register], "ANDR", false),
+    ("OR", &[Register, Value], "OR", false),
+    ("OR", &[Value, Register], "OR", true),
+    ("OR", &[Register, Register], "ORR", false),
+    ("XOR", &[Register, Value], "XOR", false),
+    ("XOR", &[Value, Register], "XOR", true),
+    ("XOR", &[Register, Register], "XORR", false),
+
+    // compare acc with the first operand, or the first operand with the second
+    ("BREQ", &[Value, Value], "BREQ", false),
+    ("BREQ", &[Register, Value], "BREQR", false),
+    ("BREQ", &[Register, Value, Value], "BREQRW", false),
+    ("BREQ", &[Register, Register, Value], "BREQRR", false),
+    ("BRNQ", &[Value, Value], "BRNQ", false),
+    ("BRNQ", &[Register, Value], "BRNQR", false),
+    ("BRNQ", &[Register, Value, Value], "BRNQRW", false),
+    ("BRNQ", &[Register, Register, Value], "BRNQRR", false),
+    ("BRLT", &[Value, Value], "BRLT", false),
+    ("BRLT", &[Register, Value], "BRLTR", false),
+    ("BRLT", &[Register, Value, Value], "BRLTRW", false),
+    ("BRLT", &[Register, Register, Value], "BRLTRR", false),
+    ("BRGT", &[Value, Value], "BRGT", false),
+    ("BRGT", &[Register, Value], "BRGTR", false),
+    ("BRGT", &[Register, Value, Value], "BRGTRW", false),
+    ("BRGT", &[Register, Register, Value], "BRGTRR", false),
+    ("BRLTE", &[Value, Value], "BRLTE", false),
+    ("BRLTE", &[Register, Value], "BRLTER", false),
+    ("BRLTE", &[Register, Value, Value], "BRLTERW", false),
+    ("BRLTE", &[Register, Register, Value], "BRLTERR", false),
+    ("BRGTE", &[Value, Value], "BRGTE", false),
+    ("BRGTE", &[Register, Value], "BRGTER", false),
+    ("BRGTE", &[Register, Value, Value], "BRGTERW", false),
+    ("BRGTE", &[Register, Register, Value], "BRGTERR", false),
+];
+
+/// Whether `mnemonic` picks its instruction from the operands, like `MOV` or `BREQ`.
+pub fn is_generic(mnemonic: &str) -> bool {
+    VARIANTS
+        .iter()
+        .any(|(generic, ..)| generic.eq_ignore_ascii_case(mnemonic))
+}
+
+/// Parses an operand of a generic instruction into its form and the instruction operands
+/// it stands for.
+fn parse_form(operand: &str, span: Span) -> Result<(Form, Vec<Operand>), String> {
+    let value = |expr: Expr| Operand {
+        kind: OperandKind::Value(expr),
+        span,
+    };
+    let register = |offset: Word| Operand {
+        kind: OperandKind::Register(offset),
+        span,
+    };
+
+    let inner = match operand.strip_prefix('[') {
+        Some(inner) => inner
+            .strip_suffix(']')
+            .ok_or_else(|| format!("unterminated memory operand `{}`", operand))?
+            .trim(),
+        None => {
+            return Ok(match parse_operand(operand)? {
+                OperandKind::Register(offset) => (Register, vec![register(offset)]),
+                OperandKind::Value(expr) => (Value, vec![value(expr)]),
+            })
+        }
+    };
+
+    // a register optionally followed by `+ offset` or `- offset`
+    let end = inner.find(|c| !is_identifier(c)).unwrap_or(inner.len());
+    let base = isa::register_offset(&inner[..end]);
+    let rest = inner[end..].trim_start();
+
+    match (base, rest.chars().next()) {
+        (Some(offset), None) => Ok((Pointer, vec![register(offset)])),
+        (Some(offset), Some(sign @ '+')) | (Some(offset), Some(sign @ '-')) => {
+            let mut expr = parse_expression(&rest[1..])?;
+            if sign == '-' {
+                expr = Expr::Unary(UnaryOp::Neg, Box::new(expr));
+            }
+            Ok((Offset, vec![register(offset), value(expr)]))
+        }
+        _ => Ok((Memory, vec![value(parse_expression(inner)?)])),
+    }
+}
+
+/// Parses a generic instruction and picks the variant matching the written operands.
+pub fn parse_generic(
+    line: &SourceLine,
+    mnemonic: &str,
+    mnemonic_span: Span,
+    operands: Vec<(&str, Span)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Statement> {
+    let mut failed = false;
+    let mut forms = Vec::new();
+    let mut parsed = Vec::new();
+    for (operand, span) in &operands {
+        // a bad operand still has a form, so the instruction gets its full size
+        let (form, operands) = parse_form(operand, *span).unwrap_or_else(|message| {
+            diagnostics.push(line.error(*span, message));
+            failed = true;
+            let form = match operand.starts_with('[') {
+                true => Memory,
+                false => Value,
+            };
+            let zero = Operand {
+                kind: OperandKind::Value(Expr::Number(0)),
+                span: *span,
+            };
+            (form, vec![zero])
+        });
+        forms.push(form);
+        parsed.push(operands);
+    }
+
+    let variants: Vec<_> = VARIANTS
+        .iter()
+        .filter(|(generic, ..)| generic.eq_ignore_ascii_case(mnemonic))
+        .collect();
+    let (_, _, instruction, reversed) =
+        match variants.iter().find(|(_, f, ..)| *f == forms.as_slice()) {
+            Some(variant) => variant,
+            None if failed => return None,
+            None => {
+                let names = |forms: &[Form]| {
+                    forms
+                        .iter()
+                        .map(|f| f.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let expected: Vec<String> = variants
+                    .iter()
+                    .map(|(_, forms, ..)| format!("`{}`", names(forms)))
+                    .collect();
+                let span = match (operands.first(), operands.last()) {
+                    (Some((_, first)), Some((_, last))) => Span::new(first.start, last.end),
+                    _ => mnemonic_span,
+                };
+                diagnostics.push(line.error(
+                    span,
+                    format!(
+                        "{} can't take `{}`, only {}",
+                        mnemonic.to_uppercase(),
+                        names(&forms),
+                        expected.join(" or ")
+                    ),
+                ));
+                return None;
+            }
+        };
+
+    if *reversed {
+        parsed.reverse();
+    }
+
+    Some(Statement::Instruction(Instruction {
+        definition: isa::from_mnemonic(instruction)?,
+        operands: parsed.into_iter().flatten().collect(),
+    }))
+}
@@ -0,0 +1,107 @@
+use crate::expression::{is_identifier, is_identifier_start, parse_number};
+use crate::parser::{strip_comment, trim_with_offset};
+use crate::{Diagnostic, SourceLine, Span, Word};
+
+/// Layout file, lists the sections in the order they are placed in the image.
+///
+/// ```text
+/// ; a section name per line, optionally with its start address
+/// .text 0x104
+/// .rodata
+/// .data
+/// .bss
+/// ```
+pub struct Layout {
+    /// Name used for the layout in diagnostics
+    pub file_name: String,
+    pub source: String,
+}
+
+/// A section as placed in the image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub address: Word,
+    pub size: Word,
+    /// Only reserves zeroed space, bss sections at the end of the image are not part of it
+    pub bss: bool,
+}
+
+/// Position of a section without a layout file: `.text`, `.data`, other sections in order
+/// of appearance and `.bss` sections last.
+pub fn default_rank(name: &str, bss: bool) -> u8 {
+    match name {
+        ".text" => 0,
+        ".data" => 1,
+        _ if bss => 3,
+        _ => 2,
+    }
+}
+
+/// A line of the layout file.
+pub struct Placement<'a> {
+    pub name: &'a str,
+    pub address: Option<Word>,
+    pub line: SourceLine<'a>,
+    pub span: Span,
+}
+
+impl Layout {
+    pub fn parse(&self) -> (Vec<Placement<'_>>, Vec<Diagnostic>) {
+        let mut placements: Vec<Placement> = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        for (n, text) in self.source.lines().enumerate() {
+            let line = SourceLine {
+                file: &self.file_name,
+                line: n + 1,
+                text,
+                context: &[],
+            };
+
+            let (column, text) = trim_with_offset(strip_comment(text), 0);
+            let mut words = text.split_whitespace();
+            let name = match words.next() {
+                Some(name) => name,
+                None => continue,
+            };
+            let span = Span::new(column, column + name.len());
+
+            let address = words.next().map(|address| {
+                let start = column + text.find(address).unwrap_or(0);
+                parse_number(address)
+                    .map_err(|message| line.error(Span::new(start, start + address.len()), message))
+            });
+
+            if !name.starts_with(is_identifier_start) || !name.chars().all(is_identifier) {
+                diagnostics.push(line.error(span, format!("invalid section name `{}`", name)));
+            } else if words.next().is_some() {
+                diagnostics.push(line.error(
+                    Span::new(column, column + text.len()),
+                    "expected a section name and an optional address".to_string(),
+                ));
+            } else if let Some(previous) = placements.iter().find(|p| p.name == name) {
+                diagnostics.push(
+                    line.error(span, format!("section `{}` is listed twice", name))
+                        .with_note(
+                            previous
+                                .line
+                                .note(previous.span, "first listed here".to_string()),
+                        ),
+                );
+            } else {
+                match address.transpose() {
+                    Ok(address) => placements.push(Placement {
+                        name,
+                        address,
+                        line,
+                        span,
+                    }),
+                    Err(diagnostic) => diagnostics.push(diagnostic),
+                }
+            }
+        }
+
+        (placements, diagnostics)
+    }
+}
@@ -0,0 +1,184 @@
+// diagnostics are returned by value throughout, they are only created on the error path
+#![allow(clippy::result_large_err)]
+
+use std::path::PathBuf;
+
+use isa::{Devices, Executable, Segment};
+
+pub type Byte = u8;
+pub type Word = u32;
+
+mod diagnostic;
+pub use diagnostic::*;
+
+mod layout;
+pub use layout::{Layout, Section};
+
+mod listing;
+pub use listing::{DefinedSymbol, ListingLine};
+
+mod object;
+pub use object::*;
+
+mod linker;
+pub use linker::{LinkMap, Linked, MapSymbol, Piece};
+
+mod analysis;
+mod assembler;
+mod directives;
+mod expression;
+mod generic;
+mod macros;
+mod parser;
+mod preprocessor;
+mod pseudo;
+mod scope;
+mod symbols;
+
+pub struct Options {
+    /// Name used for the source in diagnostics, relative includes are looked up next to it
+    pub file_name: String,
+    /// Directories searched by `.include` and `.incbin`, in order
+    pub include_paths: Vec<PathBuf>,
+    /// Address the image is loaded at, see `vm --print-base`
+    pub base: Option<Word>,
+    /// Order and addresses of the sections, by default `.text`, `.data`, other sections and `.bss`
+    pub layout: Option<Layout>,
+    /// Label execution starts at, the base if not given
+    pub entry: Option<String>,
+    /// Names and values of constants defined before the first line, like `-D NAME=value`
+    pub defines: Vec<(String, String)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            file_name: "<source>".to_string(),
+            include_paths: Vec::new(),
+            base: None,
+            layout: None,
+            entry: None,
+            defines: Vec::new(),
+        }
+    }
+}
+
+pub struct Output {
+    pub image: Vec<Byte>,
+    /// Address the first byte of the image has to be loaded at
+    pub base: Word,
+    /// Address execution starts at, see [`Options::entry`]
+    pub entry: Word,
+    /// The sections in the order they were placed
+    pub sections: Vec<Section>,
+    /// Address and bytes of every source line, see [`Output::listing`]
+    pub listing: Vec<ListingLine>,
+    /// Labels and constants, sorted by value
+    pub symbols: Vec<DefinedSymbol>,
+    /// Address of every `.proc` and the number of words its `.local`s push, sorted by address
+    pub locals: Vec<(Word, usize)>,
+    /// Every error and warning found, in source order
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Output {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+
+    /// Returns the image if no errors were found, otherwise all diagnostics.
+    pub fn into_result(self) -> Result<Vec<Byte>, Vec<Diagnostic>> {
+        if self.has_errors() {
+            Err(self.diagnostics)
+        } else {
+            Ok(self.image)
+        }
+    }
+
+    /// The image with a header for the VM, every section is a segment.
+    pub fn executable(&self, devices: Devices) -> Executable {
+        let segments = self
+            .sections
+            .iter()
+            .filter(|section| section.size > 0)
+            .map(|section| {
+                // sections of a program that failed to assemble may not fit in the image
+                let start =
+                    (section.address.saturating_sub(self.base) as usize).min(self.image.len());
+                let end = start
+                    .saturating_add(section.size as usize)
+                    .min(self.image.len());
+                match section.bss {
+                    true => Segment {
+                        address: section.address,
+                        data: Vec::new(),
+                        bss_size: section.size,
+                    },
+                    false => Segment {
+                        address: section.address,
+                        data: self.image[start..end].to_vec(),
+                        bss_size: 0,
+                    },
+                }
+            })
+            .collect();
+
+        Executable {
+            entry: self.entry,
+            load_address: self.base,
+            devices,
+            segments,
+        }
+    }
+}
+
+pub struct ObjectOutput {
+    pub object: Object,
+    /// Every error and warning found, in source order
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ObjectOutput {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+
+    /// Returns the object if no errors were found, otherwise all diagnostics.
+    pub fn into_result(self) -> Result<Object, Vec<Diagnostic>> {
+        if self.has_errors() {
+            Err(self.diagnostics)
+        } else {
+            Ok(self.object)
+        }
+    }
+}
+
+/// Assembles `source` into a binary image, collecting every diagnostic instead of
+/// stopping at the first error.
+pub fn assemble(source: &str) -> Result<Vec<Byte>, Vec<Diagnostic>> {
+    assemble_with(source, &Options::default()).into_result()
+}
+
+pub fn assemble_with(source: &str, options: &Options) -> Output {
+    assembler::assemble(source, options)
+}
+
+/// Assembles `source` into a relocatable object, see [`link`].
+pub fn assemble_object(source: &str, options: &Options) -> ObjectOutput {
+    assembler::assemble_object(source, options)
+}
+
+/// Warns about problems in the control flow of an assembled program: unreachable code,
+/// execution running into data or past the image, unbalanced `PUSH` and `POP`, `RET`
+/// without a `CALL` and jumps into data or the middle of an instruction.
+pub fn check(output: &Output) -> Vec<Diagnostic> {
+    analysis::check(output)
+}
+
+/// Links objects, each with the name used for it in diagnostics, into a binary image.
+///
+/// Sections with the same name are merged in the order of the objects, then placed like
+/// the sections of a single source. Only `file_name` and `include_paths` of the options are ignored.
+pub fn link(objects: &[(String, Object)], options: &Options) -> Linked {
+    linker::link(objects, options)
+}
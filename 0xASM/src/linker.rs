@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::assembler::{entry_point, fits, gcd};
+use crate::layout::default_rank;
+use crate::object::{Object, ObjectSymbol, SymbolValue, Target};
+use crate::{Byte, DefinedSymbol, Diagnostic, Options, Output, Section, Word};
+
+pub struct Linked {
+    pub output: Output,
+    pub map: LinkMap,
+}
+
+/// Where the linker placed every section and symbol, written by `asm link --map`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinkMap {
+    /// The sections in the order they were placed
+    pub sections: Vec<Section>,
+    /// The parts of the sections, by object
+    pub pieces: Vec<Piece>,
+    /// Every label, sorted by address
+    pub symbols: Vec<MapSymbol>,
+}
+
+/// The part of a section that comes from one object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Piece {
+    pub section: String,
+    pub object: String,
+    pub address: Word,
+    pub size: Word,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapSymbol {
+    pub name: String,
+    pub address: Word,
+    pub object: String,
+    pub global: bool,
+}
+
+impl fmt::Display for LinkMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Sections")?;
+        for section in &self.sections {
+            let bss = if section.bss { " (bss)" } else { "" };
+            writeln!(
+                f,
+                "  0x{:08X} 0x{:08X} {}{}",
+                section.address, section.size, section.name, bss
+            )?;
+            for piece in self.pieces.iter().filter(|p| p.section == section.name) {
+                writeln!(
+                    f,
+                    "    0x{:08X} 0x{:08X} {}",
+                    piece.address, piece.size, piece.object
+                )?;
+            }
+        }
+
+        writeln!(f, "\nSymbols")?;
+        for symbol in &self.symbols {
+            let local = if symbol.global { "" } else { " (local)" };
+            writeln!(
+                f,
+                "  0x{:08X} {:<24} {}{}",
+                symbol.address, symbol.name, symbol.object, local
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The sections of the same name from every object.
+struct Merged<'a> {
+    name: &'a str,
+    bss: bool,
+    alignment: Word,
+    origin: Option<Word>,
+    size: Word,
+    data: Vec<Byte>,
+    /// First object containing the section, for diagnostics
+    object: &'a str,
+}
+
+/// Decides the order of the sections like the assembler does, from the layout file or by default.
+fn order(
+    merged: &mut [Merged],
+    options: &Options,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<usize> {
+    let layout = match &options.layout {
+        Some(layout) => layout,
+        None => {
+            let mut order: Vec<usize> = (0..merged.len()).collect();
+            order.sort_by_key(|&i| default_rank(merged[i].name, merged[i].bss));
+            return order;
+        }
+    };
+
+    let (placements, errors) = layout.parse();
+    diagnostics.extend(errors);
+
+    let mut order = Vec::new();
+    for placement in placements {
+        let index = match merged.iter().position(|s| s.name == placement.name) {
+            Some(index) => index,
+            None => continue,
+        };
+        order.push(index);
+
+        let section = &mut merged[index];
+        match (section.origin, placement.address) {
+            (Some(origin), Some(address)) if origin != address => {
+                diagnostics.push(placement.line.error(
+                    placement.span,
+                    format!(
+                        "section `{}` is placed at 0x{:X}, but starts with .org 0x{:X} in {}",
+                        section.name, address, origin, section.object
+                    ),
+                ));
+            }
+            (None, Some(address)) => section.origin = Some(address),
+            _ => {}
+        }
+    }
+
+    for (index, section) in merged.iter().enumerate() {
+        if !order.contains(&index) {
+            diagnostics.push(Diagnostic::file_error(
+                section.object,
+                format!(
+                    "section `{}` is missing from the layout file {}",
+                    section.name, layout.file_name
+                ),
+            ));
+        }
+    }
+
+    order
+}
+
+/// Links the objects into a binary image.
+///
+/// Sections of the same name are merged in the order of the objects, each part aligned
+/// to its own alignment. The merged sections are placed, then every relocation is filled
+/// in with the address of its section or global symbol.
+pub fn link(objects: &[(String, Object)], options: &Options) -> Linked {
+    let mut diagnostics = Vec::new();
+
+    // merged section and offset inside of it, by object and section index
+    let mut merged: Vec<Merged> = Vec::new();
+    let mut pieces: Vec<Vec<(usize, Word)>> = Vec::new();
+    for (file, object) in objects {
+        let mut offsets = Vec::new();
+        for section in &object.sections {
+            let index = match merged.iter().position(|m| m.name == section.name) {
+                Some(index) => index,
+                None => {
+                    merged.push(Merged {
+                        name: &section.name,
+                        bss: section.bss,
+                        alignment: 1,
+                        origin: None,
+                        size: 0,
+                        data: Vec::new(),
+                        object: file,
+                    });
+                    merged.len() - 1
+                }
+            };
+            let target = &mut merged[index];
+
+            if target.bss != section.bss {
+                let (bss, data) = match target.bss {
+                    true => (target.object, file.as_str()),
+                    false => (file.as_str(), target.object),
+                };
+                diagnostics.push(Diagnostic::file_error(
+                    file,
+                    format!(
+                        "section `{}` only reserves space in {}, but has data in {}",
+                        section.name, bss, data
+                    ),
+                ));
+            }
+
+            if let Some(origin) = section.origin {
+                if target.size == 0 && target.origin.is_none() {
+                    target.origin = Some(origin);
+                } else {
+                    diagnostics.push(Diagnostic::file_error(
+                        file,
+                        format!(
+                            "section `{}` starts with .org 0x{:X}, but continues the section of {}",
+                            section.name, origin, target.object
+                        ),
+                    ));
+                }
+            }
+
+            // objects are untrusted, their sizes and alignments may not add up
+            let alignment = section.alignment.max(1);
+            match (target.alignment / gcd(target.alignment, alignment)).checked_mul(alignment) {
+                Some(alignment) => target.alignment = alignment,
+                None => diagnostics.push(Diagnostic::file_error(
+                    file,
+                    format!(
+                        "section `{}` can't be aligned to 0x{:X} and to the alignments before",
+                        section.name, alignment
+                    ),
+                )),
+            }
+            let placed = match target.size % alignment {
+                0 => Some(target.size),
+                rest => target.size.checked_add(alignment - rest),
+            }
+            .and_then(|offset| Some((offset, offset.checked_add(section.size)?)));
+            let (offset, size) = match placed {
+                Some(placed) => placed,
+                None => {
+                    diagnostics.push(Diagnostic::file_error(
+                        file,
+                        format!(
+                            "section `{}` of {} byte(s) does not fit in the address space",
+                            section.name, section.size
+                        ),
+                    ));
+                    offsets.push((index, target.size));
+                    continue;
+                }
+            };
+            target.size = size;
+            if !target.bss {
+                target.data.resize(offset as usize, 0);
+                target.data.extend_from_slice(&section.data);
+                target.data.resize(target.size as usize, 0);
+            }
+
+            offsets.push((index, offset));
+        }
+        pieces.push(offsets);
+    }
+
+    // global symbols by name and the object defining them
+    let mut globals: HashMap<&str, (usize, &ObjectSymbol)> = HashMap::new();
+    for (index, (file, object)) in objects.iter().enumerate() {
+        for symbol in &object.symbols {
+            if !symbol.global || symbol.value == SymbolValue::Extern {
+                continue;
+            }
+
+            match globals.get(symbol.name.as_str()) {
+                Some((first, previous)) => diagnostics.push(Diagnostic::file_error(
+                    file,
+                    format!(
+                        "symbol `{}` is defined twice, at {} in {} and at {} in {}",
+                        symbol.name, previous.location, objects[*first].0, symbol.location, file
+                    ),
+                )),
+                None => {
+                    globals.insert(&symbol.name, (index, symbol));
+                }
+            }
+        }
+    }
+
+    let order = order(&mut merged, options, &mut diagnostics);
+
+    // the first section decides the base if none was given
+    let base = options
+        .base
+        .unwrap_or_else(|| order.first().and_then(|&i| merged[i].origin).unwrap_or(0));
+
+    let mut address = base;
+    let mut starts = vec![0; merged.len()];
+    let mut placed: Vec<Section> = Vec::new();
+    for &index in &order {
+        let section = &merged[index];
+        let start = match section.origin {
+            Some(origin) if origin < address => {
+                diagnostics.push(Diagnostic::file_error(
+                    section.object,
+                    format!(
+                        "section `{}` at 0x{:X} is before the current address 0x{:X}",
+                        section.name, origin, address
+                    ),
+                ));
+                address
+            }
+            Some(origin) => origin,
+            None => match address % section.alignment {
+                0 => address,
+                // past the end of the address space, reported below
+                rest => address
+                    .checked_add(section.alignment - rest)
+                    .unwrap_or(Word::MAX),
+            },
+        };
+        let end = match start.checked_add(section.size) {
+            Some(end) => end,
+            None => {
+                diagnostics.push(Diagnostic::file_error(
+                    section.object,
+                    format!(
+                        "section `{}` of {} byte(s) at 0x{:X} does not fit in the address space",
+                        section.name, section.size, start
+                    ),
+                ));
+                Word::MAX
+            }
+        };
+
+        starts[index] = start;
+        placed.push(Section {
+            name: section.name.to_string(),
+            address: start,
+            size: end - start,
+            bss: section.bss,
+        });
+        address = end;
+    }
+
+    let address_of = |object: usize, value: &SymbolValue| match value {
+        SymbolValue::Address(section, offset) => {
+            let (index, start) = pieces[object][*section];
+            Some(starts[index].wrapping_add(start).wrapping_add(*offset))
+        }
+        SymbolValue::Absolute(value) => Some(*value),
+        SymbolValue::Extern => None,
+    };
+
+    for (index, (file, object)) in objects.iter().enumerate() {
+        for relocation in &object.relocations {
+            let target = match &relocation.target {
+                Target::Section(section) => address_of(index, &SymbolValue::Address(*section, 0)),
+                Target::Symbol(name) => globals
+                    .get(name.as_str())
+                    .and_then(|(owner, symbol)| address_of(*owner, &symbol.value)),
+            };
+            let value = match target {
+                Some(target) => target.wrapping_add(relocation.addend),
+                None => {
+                    let name = match &relocation.target {
+                        Target::Symbol(name) => name.as_str(),
+                        Target::Section(_) => "",
+                    };
+                    diagnostics.push(Diagnostic::file_error(
+                        file,
+                        format!(
+                            "undefined symbol `{}`, referenced at {}",
+                            name, relocation.location
+                        ),
+                    ));
+                    continue;
+                }
+            };
+
+            let width = relocation.width as usize;
+            if !fits(value, width) {
+                diagnostics.push(Diagnostic::file_error(
+                    file,
+                    format!(
+                        "value 0x{:X} does not fit into {} byte(s), referenced at {}",
+                        value, width, relocation.location
+                    ),
+                ));
+            }
+
+            let (section, start) = pieces[index][relocation.section];
+            let offset = start.checked_add(relocation.offset).map(|o| o as usize);
+            match offset.and_then(|o| merged[section].data.get_mut(o..o + width)) {
+                Some(bytes) => bytes.copy_from_slice(&value.to_le_bytes()[..width]),
+                None => diagnostics.push(Diagnostic::file_error(
+                    file,
+                    format!(
+                        "relocation at {} is outside of section `{}`",
+                        relocation.location, merged[section].name
+                    ),
+                )),
+            }
+        }
+    }
+
+    // bss sections after the last initialized byte are not part of the image
+    let end = placed
+        .iter()
+        .filter(|s| !s.bss && s.size > 0)
+        .map(|s| s.address.saturating_add(s.size))
+        .max()
+        .unwrap_or(base);
+
+    let mut image: Vec<Byte> = Vec::with_capacity(end.saturating_sub(base) as usize);
+    for (&index, section) in order.iter().zip(&placed) {
+        if section.address >= end {
+            break;
+        }
+        image.resize((section.address - base) as usize, 0);
+        image.extend_from_slice(&merged[index].data);
+    }
+    image.resize(end.saturating_sub(base) as usize, 0);
+
+    let mut map = LinkMap {
+        sections: placed.clone(),
+        ..LinkMap::default()
+    };
+    for &index in &order {
+        for ((file, object), offsets) in objects.iter().zip(&pieces) {
+            for (section, &(merged_index, offset)) in object.sections.iter().zip(offsets) {
+                if merged_index == index {
+                    map.pieces.push(Piece {
+                        section: section.name.clone(),
+                        object: file.clone(),
+                        address: starts[index].wrapping_add(offset),
+                        size: section.size,
+                    });
+                }
+            }
+        }
+    }
+    for (index, (file, object)) in objects.iter().enumerate() {
+        for symbol in &object.symbols {
+            if let SymbolValue::Address(..) = symbol.value {
+                map.symbols.push(MapSymbol {
+                    name: symbol.name.clone(),
+                    address: address_of(index, &symbol.value).unwrap_or(0),
+                    object: file.clone(),
+                    global: symbol.global,
+                });
+            }
+        }
+    }
+    map.symbols
+        .sort_by(|a, b| (a.address, &a.name).cmp(&(b.address, &b.name)));
+
+    let symbols: Vec<DefinedSymbol> = map
+        .symbols
+        .iter()
+        .map(|symbol| DefinedSymbol {
+            name: symbol.name.clone(),
+            value: symbol.address,
+            constant: false,
+        })
+        .collect();
+    let files: Vec<&str> = objects.iter().map(|(file, _)| file.as_str()).collect();
+    let entry = entry_point(&symbols, options, base, &files.join(", "), &mut diagnostics);
+
+    Linked {
+        output: Output {
+            image,
+            base,
+            entry,
+            sections: placed,
+            // the source lines are only known to the assembler
+            listing: Vec::new(),
+            symbols,
+            // procedures are only known to the assembler as well
+            locals: Vec::new(),
+            diagnostics,
+        },
+        map,
+    }
+}
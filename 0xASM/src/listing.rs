@@ -0,0 +1,108 @@
+use isa::{DebugInfo, LineInfo, SymbolInfo};
+
+use crate::{Byte, Output, Word};
+
+/// Bytes shown per row of the listing, longer lines continue on the next rows.
+const BYTES_PER_ROW: usize = 8;
+
+/// A line after preprocessing with its address and the bytes it emitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListingLine {
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    pub text: String,
+    /// Part of a macro expansion, the line number is the one inside the macro
+    pub expanded: bool,
+    pub address: Word,
+    /// Empty for labels, directives without data and lines in bss sections
+    pub bytes: Vec<Byte>,
+}
+
+/// A label or constant and its value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefinedSymbol {
+    pub name: String,
+    pub value: Word,
+    pub constant: bool,
+}
+
+fn hex(bytes: &[Byte]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Output {
+    /// Text listing with the address, the bytes and the text of every line, followed by
+    /// the symbols. Lines expanded from macros are marked with `+`.
+    pub fn listing(&self) -> String {
+        let mut listing = String::new();
+        let mut file = None;
+
+        for line in &self.listing {
+            // included files start with their name
+            if file != Some(&line.file) {
+                file = Some(&line.file);
+                listing.push_str(&format!("{}\n", line.file));
+            }
+
+            let mut rows = line.bytes.chunks(BYTES_PER_ROW);
+            let marker = if line.expanded { '+' } else { ' ' };
+            let row = format!(
+                "{:>5}{} {:08X}  {:<width$}  {}",
+                line.line,
+                marker,
+                line.address,
+                hex(rows.next().unwrap_or(&[])),
+                line.text,
+                width = BYTES_PER_ROW * 3 - 1
+            );
+            listing.push_str(row.trim_end());
+            listing.push('\n');
+
+            for (i, bytes) in rows.enumerate() {
+                let address = line.address as usize + (i + 1) * BYTES_PER_ROW;
+                listing.push_str(&format!("       {:08X}  {}\n", address, hex(bytes)));
+            }
+        }
+
+        listing.push_str("\nSymbols\n");
+        for symbol in &self.symbols {
+            let constant = if symbol.constant { " (constant)" } else { "" };
+            listing.push_str(&format!(
+                "  {:08X}  {}{}\n",
+                symbol.value, symbol.name, constant
+            ));
+        }
+
+        listing
+    }
+    /// Labels and the lines that emitted bytes, for the VM to show where it is.
+    pub fn debug_info(&self) -> DebugInfo {
+        DebugInfo {
+            lines: self
+                .listing
+                .iter()
+                .filter(|line| !line.bytes.is_empty())
+                .map(|line| LineInfo {
+                    address: line.address,
+                    size: line.bytes.len() as Word,
+                    file: line.file.clone(),
+                    line: line.line,
+                })
+                .collect(),
+            symbols: self
+                .symbols
+                .iter()
+                .filter(|symbol| !symbol.constant)
+                .map(|symbol| SymbolInfo {
+                    name: symbol.name.clone(),
+                    address: symbol.value,
+                })
+                .collect(),
+        }
+    }
+}
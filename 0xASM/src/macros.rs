@@ -0,0 +1,292 @@
+use crate::expression::{is_identifier, is_identifier_start};
+use crate::parser::trim_with_offset;
+use crate::preprocessor::{first_word, Line};
+use crate::{Diagnostic, SourceLine, Span};
+
+pub struct Macro {
+    pub name: String,
+    /// Names and default values of the parameters
+    parameters: Vec<(String, Option<String>)>,
+    body: Vec<Line>,
+    /// Labels defined in the body, every expansion gets its own copy of them
+    locals: Vec<String>,
+
+    /// The `.macro` line and the span of the name in it
+    pub definition: Line,
+    pub span: Span,
+}
+
+pub fn is_name(name: &str) -> bool {
+    name.starts_with(is_identifier_start) && name.chars().all(is_identifier)
+}
+
+/// Labels defined in a body with a literal name, labels built from parameters are not local.
+fn local_labels(body: &[Line]) -> Vec<String> {
+    body.iter()
+        .filter_map(|line| {
+            let (word, _, _) = first_word(&line.text);
+            word.strip_prefix(':')
+                .filter(|l| !l.contains('\\'))
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+/// Calls `f` for every identifier outside of literals and comments, `f` gets the name and
+/// whether it was written as `\name` and returns the replacement, if any.
+pub fn replace_names(text: &str, mut f: impl FnMut(&str, bool) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut previous = ' ';
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else if c == '\'' || c == '"' {
+            quote = Some(c);
+        } else if c == ';' {
+            result.push_str(&text[i..]);
+            break;
+        } else if (c == '\\' || is_identifier_start(c)) && !is_identifier(previous) {
+            let start = if c == '\\' { i + 1 } else { i };
+            let end = text[start..]
+                .find(|c| !is_identifier(c))
+                .map_or(text.len(), |end| start + end);
+
+            if start < end && text[start..].starts_with(is_identifier_start) {
+                let name = &text[start..end];
+                match f(name, c == '\\') {
+                    Some(replacement) => result.push_str(&replacement),
+                    None => result.push_str(&text[i..end]),
+                }
+
+                while chars.next_if(|&(j, _)| j < end).is_some() {}
+                previous = 'a';
+                continue;
+            }
+        }
+
+        result.push(c);
+        previous = c;
+    }
+
+    result
+}
+
+impl Macro {
+    /// Parses `.macro NAME param, param=default` and checks the body for unknown parameters.
+    pub fn parse(
+        definition: Line,
+        directive: Span,
+        rest: &str,
+        body: Vec<Line>,
+    ) -> Result<Macro, Vec<Diagnostic>> {
+        let line = definition.source();
+        let (column, rest) = trim_with_offset(rest, directive.end);
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let name = &rest[..end];
+        let span = Span::new(column, column + end);
+
+        if name.is_empty() {
+            return Err(vec![
+                line.error(directive, "expected macro name".to_string())
+            ]);
+        } else if !is_name(name) || name.starts_with('.') {
+            return Err(vec![
+                line.error(span, format!("invalid macro name `{}`", name))
+            ]);
+        }
+
+        let mut diagnostics = Vec::new();
+        let mut parameters: Vec<(String, Option<String>)> = Vec::new();
+        let parameter_list = &rest[end..];
+        if !parameter_list.trim().is_empty() {
+            for (parameter, span) in crate::parser::split_operands(parameter_list, span.end) {
+                let (parameter, default) = match parameter.find('=') {
+                    Some(i) => (
+                        parameter[..i].trim_end(),
+                        Some(parameter[i + 1..].trim_start().to_string()),
+                    ),
+                    None => (parameter, None),
+                };
+
+                if !is_name(parameter) {
+                    diagnostics
+                        .push(line.error(span, format!("invalid parameter name `{}`", parameter)));
+                } else if parameters.iter().any(|(p, _)| p == parameter) {
+                    diagnostics.push(
+                        line.error(span, format!("parameter `{}` is given twice", parameter)),
+                    );
+                } else {
+                    parameters.push((parameter.to_string(), default));
+                }
+            }
+        }
+
+        for body_line in &body {
+            replace_names(&body_line.text, |found, parameter| {
+                if parameter && !parameters.iter().any(|(p, _)| p == found) {
+                    let reference = format!("\\{}", found);
+                    let column = body_line.text.find(&reference).unwrap_or(0);
+                    diagnostics.push(body_line.source().error(
+                        Span::new(column, column + reference.len()),
+                        format!("macro `{}` has no parameter `{}`", name, found),
+                    ));
+                }
+                None
+            });
+        }
+
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        Ok(Macro {
+            name: name.to_string(),
+            parameters,
+            locals: local_labels(&body),
+            body,
+            definition,
+            span,
+        })
+    }
+
+    /// Expands an invocation. `id` is unique for every expansion and used to rename local labels.
+    pub fn expand(
+        &self,
+        call: &SourceLine,
+        span: Span,
+        mut arguments: Vec<(&str, Span)>,
+        id: usize,
+    ) -> Result<Vec<Line>, Diagnostic> {
+        if arguments.len() == 1 && arguments[0].0.is_empty() {
+            arguments.clear();
+        }
+
+        let mut values: Vec<Option<String>> = vec![None; self.parameters.len()];
+        let mut position = 0;
+        for (argument, argument_span) in arguments {
+            // arguments can be given by name as `name=value`
+            let named = argument.find('=').and_then(|i| {
+                let name = argument[..i].trim_end();
+                let value = argument[i + 1..].trim_start();
+                is_name(name).then_some((name, value))
+            });
+
+            let (index, value) = match named {
+                Some((name, value)) => match self.parameters.iter().position(|(p, _)| p == name) {
+                    Some(index) => (index, value),
+                    None => {
+                        return Err(call.error(
+                            argument_span,
+                            format!("macro `{}` has no parameter `{}`", self.name, name),
+                        ))
+                    }
+                },
+                None => {
+                    position += 1;
+                    (position - 1, argument)
+                }
+            };
+
+            if index >= self.parameters.len() {
+                return Err(call.error(
+                    argument_span,
+                    format!(
+                        "macro `{}` expects at most {} argument(s)",
+                        self.name,
+                        self.parameters.len()
+                    ),
+                ));
+            } else if values[index].is_some() {
+                return Err(call.error(
+                    argument_span,
+                    format!("argument `{}` is given twice", self.parameters[index].0),
+                ));
+            }
+            values[index] = Some(value.to_string());
+        }
+
+        let values = values
+            .into_iter()
+            .zip(&self.parameters)
+            .map(|(value, (name, default))| {
+                value.or_else(|| default.clone()).ok_or_else(|| {
+                    call.error(
+                        span,
+                        format!("missing argument `{}` of macro `{}`", name, self.name),
+                    )
+                })
+            })
+            .collect::<Result<Vec<String>, Diagnostic>>()?;
+
+        let context = vec![call.note(span, format!("in expansion of macro `{}`", self.name))];
+        Ok(self
+            .body
+            .iter()
+            .map(|line| {
+                let text = replace_names(&line.text, |name, parameter| {
+                    if parameter {
+                        let index = self.parameters.iter().position(|(p, _)| p == name)?;
+                        Some(values[index].clone())
+                    } else if self.locals.iter().any(|l| l == name) {
+                        Some(format!("{}@{}", name, id))
+                    } else {
+                        None
+                    }
+                });
+
+                Line {
+                    file: line.file.clone(),
+                    line: line.line,
+                    text,
+                    context: context.clone(),
+                    expanded: true,
+                    listed_only: false,
+                    procedure: None,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Copies the body of a `.rept` or `.irp` block for one repetition. `\name` is replaced by
+/// the value of `.irp`, and like in macros every copy gets its own labels.
+pub fn repeat(
+    body: &[Line],
+    parameter: Option<(&str, &str)>,
+    context: &[Diagnostic],
+    id: usize,
+) -> Vec<Line> {
+    let locals = local_labels(body);
+
+    body.iter()
+        .map(|line| {
+            let text = replace_names(&line.text, |name, escaped| match parameter {
+                Some((parameter, value)) if escaped && name == parameter => Some(value.to_string()),
+                _ if !escaped && locals.iter().any(|l| l == name) => {
+                    Some(format!("{}@{}", name, id))
+                }
+                _ => None,
+            });
+
+            Line {
+                file: line.file.clone(),
+                line: line.line,
+                text,
+                context: context.to_vec(),
+                expanded: true,
+                listed_only: false,
+                procedure: None,
+            }
+        })
+        .collect()
+}
@@ -1,235 +1,438 @@
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::env;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
-use std::os::windows::prelude::FileExt;
-
-use instructions::instruction_to_byte;
-
-pub type Byte = u8;
-pub type Word = u32;
-
-mod instructions;
-
-fn write_output_byte(
-    output: &mut File,
-    data: Byte,
-    total_bytes_written: &mut u32,
-) -> Result<(), String> {
-    match output.write_all(&data.to_le_bytes()) {
-        Ok(_) => {
-            *total_bytes_written += 1;
-            Ok(())
-        }
-        Err(_) => Err("Failed to write to output file".to_string()),
-    }
-}
-
-fn write_output_word(
-    output: &mut File,
-    data: Word,
-    total_bytes_written: &mut u32,
-) -> Result<(), String> {
-    match output.write_all(&data.to_le_bytes()) {
-        Ok(_) => {
-            *total_bytes_written += 4;
-            Ok(())
-        }
-        Err(_) => Err("Failed to write to output file".to_string()),
-    }
-}
-
-fn write_parsed_number(
-    output: &mut File,
-    total_bytes_written: &mut u32,
-    operand: &str,
-    prefix: &str,
-    radix: u32,
-    name: &str,
-    n: usize,
-) -> Result<(), String> {
-    match Word::from_str_radix(operand.trim_start_matches(prefix), radix) {
-        Ok(op) => write_output_word(output, op, total_bytes_written),
-        Err(_) => Err(format!(
-            "Error parsing {} operand at line {}: {}",
-            name, n, operand
-        )),
-    }
-}
-
-fn parse_line(
-    output: &mut File,
-    mut n: usize,
-    line: &String,
-    jump_label_locations: &mut Vec<(Word, String)>,
-    total_bytes_written: &mut u32,
-) -> Result<(), String> {
-    n += 1;
-
-    let mut split = line.splitn(2, ' ');
-    let instruction = split.next();
-    if instruction.is_none() {
-        return Err(format!("Error reading instruction at line {}", n));
-    }
-
-    let instruction = instruction.unwrap();
-
-    let instruction_code = instruction_to_byte(instruction);
-    if instruction_code.is_none() {
-        return Err(format!(
-            "Unknown instruction at line {}: {}",
-            n, instruction
-        ));
-    }
-
-    // write instruction byte
-    write_output_byte(output, instruction_code.unwrap().0, total_bytes_written)?;
-
-    let operands = split.next();
-    // if we have no operands but expect one, then error
-    if operands.is_none() && instruction_code.unwrap().1 != 0 {
-        return Err(format!(
-            "Missing parameter for instruction {} at line {}",
-            instruction, n
-        ));
-    // else if we have no operands and don't expect any, then return
-    } else if operands.is_none() {
-        return Ok(());
-    }
-
-    let operands = operands.unwrap().replace(" ", "");
-    let operands_split = operands.split(',');
-    // keep track of number of operands for error checking
-    let mut operand_count = 0;
-    for operand in operands_split {
-        let op: Word;
-
-        if operand.starts_with("0x") {
-            write_parsed_number(output, total_bytes_written, operand, "0x", 16, "hex", n)?;
-        } else if operand.starts_with("0b") {
-            write_parsed_number(output, total_bytes_written, operand, "0b", 2, "bin", n)?;
-        } else if operand.to_uppercase().starts_with("R") {
-            // remove the R part of the register name
-            let reg_name = operand[1..].to_string();
-
-            let reg_num = reg_name.parse::<Word>();
-            if reg_num.is_err() {
-                return Err(format!("Error parsing register at line {}: {}", n, operand));
-            }
-
-            // check if it is inbounds and calculate the offset
-            let reg_num = reg_num.unwrap();
-            if reg_num > 0 && reg_num <= 8 {
-                op = (reg_num - 1) * 4;
-            } else {
-                return Err(format!("Invalid register at line {}: {}", n, operand));
-            }
-
-            write_output_word(output, op, total_bytes_written)?;
-        } else if operand.starts_with(":") {
-            // add the location of the label and the labels name for later and fill it with 0xFFFF FFFF FFFF FFFF
-            jump_label_locations.push((*total_bytes_written, operand[1..].to_string()));
-            write_output_word(output, Word::MAX, total_bytes_written)?;
-        } else if operand.starts_with("'") {
-            // parse char literals (single quotes) and write as little endian bytes
-            let mut op = operand.trim_matches('\'').as_bytes().to_vec();
-            for _ in 0..4 - op.len() {
-                op.push(0);
-            }
-            write_output_word(output, Word::from_le_bytes(op.clone().try_into().expect("Failed to convert char literal to Word")), total_bytes_written)?;
-        } else {
-            write_parsed_number(output, total_bytes_written, operand, "", 10, "dec", n)?;
-        }
-
-        operand_count += 1;
-    }
-
-    // of the operator count of the current instruction is not equal to the number of operands expected, then error
-    if operand_count != instruction_code.unwrap().1 {
-        return Err(format!(
-            "Wrong number of operands for instruction {} at line {}",
-            instruction, n
-        ));
-    }
-
-    Ok(())
-}
-
-fn main() -> Result<(), String> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        println!("Usage: {} <input> <output>", args.get(0).unwrap());
-        return Err("Invalid arguments".to_string());
-    }
-
-    let mut output = match File::create(args.get(2).unwrap()) {
-        Ok(file) => file,
-        Err(_) => {
-            return Err(format!(
-                "Error creating output file: {}",
-                args.get(2).unwrap()
-            ))
-        }
-    };
-    let input = match File::open(args.get(1).unwrap()) {
-        Ok(file) => BufReader::new(file),
-        Err(_) => {
-            return Err(format!(
-                "Error opening input file: {}",
-                args.get(1).unwrap()
-            ))
-        }
-    };
-
-    let mut jump_label_map: HashMap<String, Word> = HashMap::new();
-    let mut jump_label_locations: Vec<(Word, String)> = Vec::new();
-    let mut total_bytes_written: u32 = 0;
-
-    // Parse the input file
-    for (n, line) in input.lines().enumerate() {
-        if line.is_err() {
-            return Err(format!("Error reading line {}", n));
-        }
-
-        let line = line.unwrap();
-
-        if !line.is_empty() {
-            // comments with ; are allowed
-            if line.starts_with(";") {
-                continue;
-            } else if line.starts_with(":") {
-                // catch label definition and save the label plus its location in the output file
-                let label = line[1..].to_string();
-
-                jump_label_map.insert(label, total_bytes_written);
-
-                continue;
-            }
-
-            match parse_line(
-                &mut output,
-                n,
-                &line,
-                &mut jump_label_locations,
-                &mut total_bytes_written,
-            ) {
-                Ok(_) => {}
-                Err(err) => {
-                    println!("{}", err);
-                    break;
-                }
-            };
-        }
-    }
-
-    for (location, label) in jump_label_locations {
-        let address = *jump_label_map.get(&label).unwrap();
-        match output.seek_write(&address.to_le_bytes(), location as u64) {
-            Ok(_) => {}
-            Err(_) => return Err(format!("Error writing jump label {}", label)),
-        }
-    }
-
-    Ok(())
-}
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use asm::{
+    assemble_object, assemble_with, check, link, Byte, Diagnostic, Layout, Object, Options, Output,
+    Word,
+};
+use isa::{Devices, Executable};
+
+fn parse_address(address: &str) -> Option<Word> {
+    match address.strip_prefix("0x") {
+        Some(hex) => Word::from_str_radix(hex, 16).ok(),
+        None => address.parse().ok(),
+    }
+}
+
+/// Parses `NAME=value` of `-D`, the value is 1 if not given.
+fn parse_define(define: &str) -> (String, String) {
+    match define.split_once('=') {
+        Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+        None => (define.trim().to_string(), "1".to_string()),
+    }
+}
+
+/// Parses `-I <dir>` or `-I<dir>` and `-D NAME=value` or `-DNAME=value`, returns whether
+/// `arg` was one of them.
+fn parse_source_option<'a>(
+    arg: &str,
+    rest: &mut impl Iterator<Item = &'a String>,
+    include_paths: &mut Vec<PathBuf>,
+    defines: &mut Vec<(String, String)>,
+) -> Result<bool, String> {
+    if let Some(define) = arg.strip_prefix("-D") {
+        let define = match define {
+            "" => rest.next().ok_or("Missing NAME=value after -D")?,
+            define => define,
+        };
+        defines.push(parse_define(define));
+        return Ok(true);
+    }
+
+    match arg.strip_prefix("-I") {
+        Some("") => match rest.next() {
+            Some(path) => include_paths.push(PathBuf::from(path)),
+            None => return Err("Missing directory after -I".to_string()),
+        },
+        Some(path) => include_paths.push(PathBuf::from(path)),
+        None => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn read_layout(file_name: Option<&String>) -> Result<Layout, String> {
+    let file_name = file_name.ok_or("Missing file after --layout")?;
+    let source = fs::read_to_string(file_name)
+        .map_err(|_| format!("Error opening layout file: {}", file_name))?;
+    Ok(Layout {
+        file_name: file_name.clone(),
+        source,
+    })
+}
+
+/// Prints every diagnostic, not only the first one, and fails if any of them is an error.
+fn report(diagnostics: &[Diagnostic], action: &str) -> Result<(), String> {
+    for diagnostic in diagnostics {
+        eprintln!("{}\n", diagnostic);
+    }
+
+    match diagnostics.iter().filter(|d| d.is_error()).count() {
+        0 => Ok(()),
+        count => Err(format!("Failed to {} ({} errors)", action, count)),
+    }
+}
+
+/// File format of the assembled or linked program.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// The image as it is loaded into memory
+    Binary,
+    /// Header with the entry point and the sections, see `isa::Executable`
+    Executable,
+    IntelHex,
+    SRecord,
+}
+
+fn parse_format(name: Option<&String>) -> Result<Format, String> {
+    match name.map(String::as_str) {
+        Some("bin") => Ok(Format::Binary),
+        Some("exe") => Ok(Format::Executable),
+        Some("ihex") => Ok(Format::IntelHex),
+        Some("srec") => Ok(Format::SRecord),
+        _ => Err(
+            "Missing or invalid format after --format, expected bin, exe, ihex or srec".to_string(),
+        ),
+    }
+}
+
+fn parse_device(name: Option<&String>, devices: &mut Devices) -> Result<(), String> {
+    match name.map(String::as_str) {
+        Some("screen") => devices.screen = true,
+        Some("hard_drive") => devices.hard_drive = true,
+        _ => {
+            return Err(
+                "Missing or invalid device after --device, expected screen or hard_drive"
+                    .to_string(),
+            )
+        }
+    }
+
+    Ok(())
+}
+
+fn program_bytes(output: &Output, format: Format, devices: Devices) -> Vec<Byte> {
+    match format {
+        Format::Binary => output.image.clone(),
+        Format::Executable => output.executable(devices).to_bytes(),
+        Format::IntelHex => output.executable(devices).to_intel_hex().into_bytes(),
+        Format::SRecord => output.executable(devices).to_srecord().into_bytes(),
+    }
+}
+
+fn write_debug_info(file_name: &str, output: &Output) -> Result<(), String> {
+    fs::write(file_name, output.debug_info().to_string())
+        .map_err(|_| format!("Error creating debug info file: {}", file_name))
+}
+
+/// `asm link [--base <address>] [--layout <file>] [--format <bin|exe|ihex|srec>] [--entry <label>] [--device <name>]...
+///           [--map <file>] [--debug-info <file>] -o <output> <objects>...`
+fn link_command(args: &[String]) -> Result<(), String> {
+    let mut options = Options::default();
+    let mut format = Format::Binary;
+    let mut devices = Devices::default();
+    let mut map = None;
+    let mut debug_info = None;
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--base" => match rest.next().and_then(|address| parse_address(address)) {
+                Some(address) => options.base = Some(address),
+                None => return Err("Missing or invalid address after --base".to_string()),
+            },
+            "--layout" => options.layout = Some(read_layout(rest.next())?),
+            "--format" => format = parse_format(rest.next())?,
+            "--entry" => {
+                options.entry = Some(rest.next().ok_or("Missing label after --entry")?.clone())
+            }
+            "--device" => parse_device(rest.next(), &mut devices)?,
+            "--map" => map = Some(rest.next().ok_or("Missing file after --map")?),
+            "--debug-info" => {
+                debug_info = Some(rest.next().ok_or("Missing file after --debug-info")?)
+            }
+            "-o" => output = Some(rest.next().ok_or("Missing file after -o")?),
+            _ => files.push(arg),
+        }
+    }
+
+    let output = match output {
+        Some(output) if !files.is_empty() => output,
+        _ => {
+            println!(
+                "Usage: {} link [--base <address>] [--layout <file>] [--format <bin|exe|ihex|srec>] [--entry <label>] [--device <name>]... [--map <file>] [--debug-info <file>] -o <output> <objects>...",
+                args[0]
+            );
+            return Err("Invalid arguments".to_string());
+        }
+    };
+
+    let mut objects = Vec::new();
+    for file in files {
+        let bytes = fs::read(file).map_err(|_| format!("Error opening object file: {}", file))?;
+        let object = Object::from_bytes(&bytes).map_err(|e| format!("{}: {}", file, e))?;
+        objects.push((file.clone(), object));
+    }
+
+    let linked = link(&objects, &options);
+    report(&linked.output.diagnostics, "link")?;
+
+    if let Some(map) = map {
+        fs::write(map, linked.map.to_string())
+            .map_err(|_| format!("Error creating map file: {}", map))?;
+    }
+    // the linker only knows the symbols, not the source lines
+    if let Some(debug_info) = debug_info {
+        write_debug_info(debug_info, &linked.output)?;
+    }
+
+    // only create the output file once every object linked
+    fs::write(output, program_bytes(&linked.output, format, devices))
+        .map_err(|_| format!("Error creating output file: {}", output))
+}
+
+/// `asm disasm [--base <address>] [--debug-info <file>] <binary>`
+fn disasm_command(args: &[String]) -> Result<(), String> {
+    let mut base = None;
+    let mut debug_info = isa::DebugInfo::default();
+    let mut files = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--base" => match rest.next().and_then(|address| parse_address(address)) {
+                Some(address) => base = Some(address),
+                None => return Err("Missing or invalid address after --base".to_string()),
+            },
+            "--debug-info" => {
+                let file = rest.next().ok_or("Missing file after --debug-info")?;
+                let text = fs::read_to_string(file)
+                    .map_err(|_| format!("Error opening debug info file: {}", file))?;
+                debug_info =
+                    isa::DebugInfo::parse(&text).map_err(|e| format!("{}: {}", file, e))?;
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    if files.len() != 1 {
+        println!(
+            "Usage: {} disasm [--base <address>] [--debug-info <file>] <binary>",
+            args[0]
+        );
+        return Err("Invalid arguments".to_string());
+    }
+
+    let image =
+        fs::read(files[0]).map_err(|_| format!("Error opening input file: {}", files[0]))?;
+
+    // addresses end at 0xFFFFFFFF
+    let fits = |address: Word, size: usize| address as u64 + size as u64 <= 1 << 32;
+
+    // executables and hex files know the address of every segment, bss segments have no
+    // instructions
+    if let Some(executable) =
+        Executable::from_file(files[0], &image).map_err(|e| format!("{}: {}", files[0], e))?
+    {
+        for segment in executable.segments.iter().filter(|s| !s.data.is_empty()) {
+            if !fits(segment.address, segment.data.len()) {
+                return Err(format!(
+                    "{}: segment at 0x{:X} runs past the end of the address space",
+                    files[0], segment.address
+                ));
+            }
+            print!(
+                "{}",
+                isa::disassemble(&segment.data, segment.address, &debug_info)
+            );
+        }
+        return Ok(());
+    }
+
+    // without --base, the first label of the debug info is the best guess
+    let base = base
+        .or_else(|| debug_info.symbols.iter().map(|s| s.address).min())
+        .unwrap_or(0);
+    if !fits(base, image.len()) {
+        return Err(format!(
+            "{} bytes at 0x{:X} run past the end of the address space",
+            image.len(),
+            base
+        ));
+    }
+    print!("{}", isa::disassemble(&image, base, &debug_info));
+
+    Ok(())
+}
+
+/// `asm check [-I <dir>]... [-D <name>[=<value>]]... [--base <address>] [--layout <file>] [--entry <label>] <input>`
+fn check_command(args: &[String]) -> Result<(), String> {
+    let mut options = Options::default();
+    let mut files = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--base" => match rest.next().and_then(|address| parse_address(address)) {
+                Some(address) => options.base = Some(address),
+                None => return Err("Missing or invalid address after --base".to_string()),
+            },
+            "--layout" => options.layout = Some(read_layout(rest.next())?),
+            "--entry" => {
+                options.entry = Some(rest.next().ok_or("Missing label after --entry")?.clone())
+            }
+            _ => {
+                let (include_paths, defines) = (&mut options.include_paths, &mut options.defines);
+                if !parse_source_option(arg, &mut rest, include_paths, defines)? {
+                    files.push(arg);
+                }
+            }
+        }
+    }
+
+    if files.len() != 1 {
+        println!(
+            "Usage: {} check [-I <dir>]... [-D <name>[=<value>]]... [--base <address>] [--layout <file>] [--entry <label>] <input>",
+            args[0]
+        );
+        return Err("Invalid arguments".to_string());
+    }
+
+    let input = files[0];
+    let source =
+        fs::read_to_string(input).map_err(|_| format!("Error opening input file: {}", input))?;
+    options.file_name = input.clone();
+
+    let output = assemble_with(&source, &options);
+    report(&output.diagnostics, &format!("assemble {}", input))?;
+
+    let warnings = check(&output);
+    report(&warnings, &format!("check {}", input))?;
+    match warnings.len() {
+        0 => Ok(()),
+        count => Err(format!("Found {} problems in {}", count, input)),
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        Some("link") => return link_command(&args),
+        Some("disasm") => return disasm_command(&args),
+        Some("check") => return check_command(&args),
+        _ => {}
+    }
+
+    // -I <dir> or -I<dir> adds an include path, -D NAME=value or -DNAME=value a constant,
+    // everything else is positional
+    let mut include_paths = Vec::new();
+    let mut defines = Vec::new();
+    let mut base = None;
+    let mut layout = None;
+    let mut entry = None;
+    let mut format = Format::Binary;
+    let mut devices = Devices::default();
+    let mut object = false;
+    let mut listing = None;
+    let mut debug_info = None;
+    let mut files = Vec::new();
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--base" {
+            match rest.next().and_then(|address| parse_address(address)) {
+                Some(address) => base = Some(address),
+                None => return Err("Missing or invalid address after --base".to_string()),
+            }
+            continue;
+        } else if arg == "--layout" {
+            layout = Some(read_layout(rest.next())?);
+            continue;
+        } else if arg == "--format" {
+            format = parse_format(rest.next())?;
+            continue;
+        } else if arg == "--entry" {
+            entry = Some(rest.next().ok_or("Missing label after --entry")?.clone());
+            continue;
+        } else if arg == "--device" {
+            parse_device(rest.next(), &mut devices)?;
+            continue;
+        } else if arg == "-c" {
+            object = true;
+            continue;
+        } else if arg == "--listing" {
+            listing = Some(rest.next().ok_or("Missing file after --listing")?);
+            continue;
+        } else if arg == "--debug-info" {
+            debug_info = Some(rest.next().ok_or("Missing file after --debug-info")?);
+            continue;
+        }
+
+        if !parse_source_option(arg, &mut rest, &mut include_paths, &mut defines)? {
+            files.push(arg);
+        }
+    }
+
+    // objects are only placed and given an entry point by the linker
+    let linked_only = listing.is_some()
+        || debug_info.is_some()
+        || entry.is_some()
+        || format != Format::Binary
+        || devices != Devices::default();
+    if files.len() != 2 || (object && linked_only) {
+        println!(
+            "Usage: {} [-c] [-I <dir>]... [-D <name>[=<value>]]... [--base <address>] [--layout <file>] [--format <bin|exe|ihex|srec>] [--entry <label>] [--device <name>]... [--listing <file>] [--debug-info <file>] <input> <output>",
+            args[0]
+        );
+        println!(
+            "       {} link [--base <address>] [--layout <file>] [--format <bin|exe|ihex|srec>] [--entry <label>] [--device <name>]... [--map <file>] [--debug-info <file>] -o <output> <objects>...",
+            args[0]
+        );
+        println!(
+            "       {} disasm [--base <address>] [--debug-info <file>] <binary>",
+            args[0]
+        );
+        println!(
+            "       {} check [-I <dir>]... [-D <name>[=<value>]]... [--base <address>] [--layout <file>] [--entry <label>] <input>",
+            args[0]
+        );
+        return Err("Invalid arguments".to_string());
+    }
+
+    let input = files[0];
+    let source = match fs::read_to_string(input) {
+        Ok(source) => source,
+        Err(_) => return Err(format!("Error opening input file: {}", input)),
+    };
+
+    let options = Options {
+        file_name: input.clone(),
+        include_paths,
+        base,
+        layout,
+        entry,
+        defines,
+    };
+
+    // only create the output file once the whole program assembled
+    let bytes = if object {
+        let output = assemble_object(&source, &options);
+        report(&output.diagnostics, &format!("assemble {}", input))?;
+        output.object.to_bytes()
+    } else {
+        let output = assemble_with(&source, &options);
+        report(&output.diagnostics, &format!("assemble {}", input))?;
+        if let Some(listing) = listing {
+            fs::write(listing, output.listing())
+                .map_err(|_| format!("Error creating listing file: {}", listing))?;
+        }
+        if let Some(debug_info) = debug_info {
+            write_debug_info(debug_info, &output)?;
+        }
+        program_bytes(&output, format, devices)
+    };
+
+    match fs::write(files[1], &bytes) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Error creating output file: {}", files[1])),
+    }
+}
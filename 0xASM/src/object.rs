@@ -0,0 +1,278 @@
+use crate::{Byte, Word};
+
+/// Magic bytes at the start of every object file, followed by the format version.
+const MAGIC: &[u8; 4] = b"0xOB";
+const VERSION: Byte = 1;
+
+/// Relocatable output of `asm -c`, combined into a program by `asm link`.
+///
+/// All values are little endian, strings and byte arrays are prefixed with their length.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Object {
+    pub sections: Vec<ObjectSection>,
+    pub symbols: Vec<ObjectSymbol>,
+    pub relocations: Vec<Relocation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectSection {
+    pub name: String,
+    pub bss: bool,
+    pub alignment: Word,
+    /// Fixed address from a leading `.org`
+    pub origin: Option<Word>,
+    pub size: Word,
+    /// Contents with every relocated value set to 0, empty for bss sections
+    pub data: Vec<Byte>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SymbolValue {
+    /// Offset inside a section of the object, by index
+    Address(usize, Word),
+    /// A `.global` constant
+    Absolute(Word),
+    /// Declared with `.extern`
+    Extern,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectSymbol {
+    pub name: String,
+    pub value: SymbolValue,
+    /// Visible to other objects, local labels are only kept for the link map
+    pub global: bool,
+    /// `file:line` of the definition, for diagnostics of the linker
+    pub location: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Start of a section of the same object, by index
+    Section(usize),
+    /// A global symbol of any object
+    Symbol(String),
+}
+
+/// A value the linker fills in once the addresses are known.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// Section and offset of the value inside of it
+    pub section: usize,
+    pub offset: Word,
+    /// Width of the value in bytes
+    pub width: Byte,
+    /// The value is the address of the target plus the addend
+    pub target: Target,
+    pub addend: Word,
+    /// `file:line` of the reference, for diagnostics of the linker
+    pub location: String,
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<Byte>,
+}
+
+impl Writer {
+    fn byte(&mut self, byte: Byte) {
+        self.bytes.push(byte);
+    }
+
+    fn word(&mut self, word: Word) {
+        self.bytes.extend_from_slice(&word.to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[Byte]) {
+        self.word(bytes.len() as Word);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn string(&mut self, string: &str) {
+        self.bytes(string.as_bytes());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [Byte],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [Byte], String> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or("unexpected end of object file")?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<Byte, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<Word, String> {
+        let bytes = self.take(4)?;
+        Ok(Word::from_le_bytes([
+            bytes[0], bytes[1], bytes[2], bytes[3],
+        ]))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<Byte>, String> {
+        let length = self.word()? as usize;
+        Ok(self.take(length)?.to_vec())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        String::from_utf8(self.bytes()?).map_err(|_| "invalid string in object file".to_string())
+    }
+
+    /// Reads a count followed by that many items.
+    fn list<T>(&mut self, f: impl Fn(&mut Self) -> Result<T, String>) -> Result<Vec<T>, String> {
+        let count = self.word()?;
+        (0..count).map(|_| f(self)).collect()
+    }
+}
+
+impl Object {
+    pub fn to_bytes(&self) -> Vec<Byte> {
+        let mut w = Writer::default();
+        w.bytes.extend_from_slice(MAGIC);
+        w.byte(VERSION);
+
+        w.word(self.sections.len() as Word);
+        for section in &self.sections {
+            w.string(&section.name);
+            w.byte(section.bss as Byte);
+            w.word(section.alignment);
+            match section.origin {
+                Some(origin) => {
+                    w.byte(1);
+                    w.word(origin);
+                }
+                None => w.byte(0),
+            }
+            w.word(section.size);
+            w.bytes(&section.data);
+        }
+
+        w.word(self.symbols.len() as Word);
+        for symbol in &self.symbols {
+            w.string(&symbol.name);
+            match symbol.value {
+                SymbolValue::Address(section, offset) => {
+                    w.byte(0);
+                    w.word(section as Word);
+                    w.word(offset);
+                }
+                SymbolValue::Absolute(value) => {
+                    w.byte(1);
+                    w.word(value);
+                }
+                SymbolValue::Extern => w.byte(2),
+            }
+            w.byte(symbol.global as Byte);
+            w.string(&symbol.location);
+        }
+
+        w.word(self.relocations.len() as Word);
+        for relocation in &self.relocations {
+            w.word(relocation.section as Word);
+            w.word(relocation.offset);
+            w.byte(relocation.width);
+            match &relocation.target {
+                Target::Section(section) => {
+                    w.byte(0);
+                    w.word(*section as Word);
+                }
+                Target::Symbol(name) => {
+                    w.byte(1);
+                    w.string(name);
+                }
+            }
+            w.word(relocation.addend);
+            w.string(&relocation.location);
+        }
+
+        w.bytes
+    }
+
+    pub fn from_bytes(bytes: &[Byte]) -> Result<Object, String> {
+        let mut r = Reader { bytes, position: 0 };
+        if r.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err("not an object file".to_string());
+        }
+        let version = r.byte()?;
+        if version != VERSION {
+            return Err(format!("unsupported object file version {}", version));
+        }
+
+        let sections = r.list(|r| {
+            Ok(ObjectSection {
+                name: r.string()?,
+                bss: r.byte()? != 0,
+                alignment: r.word()?,
+                origin: match r.byte()? {
+                    0 => None,
+                    _ => Some(r.word()?),
+                },
+                size: r.word()?,
+                data: r.bytes()?,
+            })
+        })?;
+
+        let symbols = r.list(|r| {
+            Ok(ObjectSymbol {
+                name: r.string()?,
+                value: match r.byte()? {
+                    0 => SymbolValue::Address(r.word()? as usize, r.word()?),
+                    1 => SymbolValue::Absolute(r.word()?),
+                    2 => SymbolValue::Extern,
+                    kind => return Err(format!("invalid symbol kind {}", kind)),
+                },
+                global: r.byte()? != 0,
+                location: r.string()?,
+            })
+        })?;
+
+        let relocations = r.list(|r| {
+            Ok(Relocation {
+                section: r.word()? as usize,
+                offset: r.word()?,
+                width: r.byte()?,
+                target: match r.byte()? {
+                    0 => Target::Section(r.word()? as usize),
+                    1 => Target::Symbol(r.string()?),
+                    kind => return Err(format!("invalid relocation target {}", kind)),
+                },
+                addend: r.word()?,
+                location: r.string()?,
+            })
+        })?;
+
+        // indices are checked once, so the linker can use them directly
+        let valid = |section: usize| section < sections.len();
+        let symbols_valid = symbols.iter().all(|s| match s.value {
+            SymbolValue::Address(section, _) => valid(section),
+            _ => true,
+        });
+        let relocations_valid = relocations.iter().all(|r| {
+            valid(r.section)
+                && matches!(r.width, 1 | 2 | 4)
+                && match r.target {
+                    Target::Section(section) => valid(section),
+                    Target::Symbol(_) => true,
+                }
+        });
+        if !symbols_valid || !relocations_valid {
+            return Err("invalid section index in object file".to_string());
+        }
+
+        Ok(Object {
+            sections,
+            symbols,
+            relocations,
+        })
+    }
+}
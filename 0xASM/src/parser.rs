@@ -0,0 +1,295 @@
+use crate::directives::{parse_directive, Data};
+use crate::expression::{is_anonymous, is_identifier, is_identifier_start, parse_expression, Expr};
+use crate::generic::{is_generic, parse_generic};
+use crate::{Byte, Diagnostic, SourceLine, Span, Word};
+
+pub enum OperandKind {
+    /// Register offset inside the VM's register file
+    Register(Word),
+    /// Numbers, labels and constants, evaluated in the second pass
+    Value(Expr),
+}
+
+pub struct Operand {
+    pub kind: OperandKind,
+    pub span: Span,
+}
+
+pub struct Instruction {
+    pub definition: &'static isa::Instruction,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /// Number of bytes the instruction occupies in the image.
+    pub fn size(&self) -> Word {
+        self.definition.size() as Word
+    }
+}
+
+pub enum Statement {
+    Label(String, Span),
+    Instruction(Instruction),
+    Data(Data),
+    /// `.equ name, value`
+    Constant(String, Span, Operand),
+    /// Switches to the named section
+    Section(String, Span),
+    /// `.global` names, exported to other object files
+    Global(Vec<(String, Span)>),
+    /// `.extern` names, defined by other object files
+    Extern(Vec<(String, Span)>),
+    /// `.struct name`, starts a struct made of the following fields
+    Struct(String, Span),
+    /// `.field name, size` inside a struct
+    Field(String, Span, Operand),
+    /// `.endstruct`
+    EndStruct,
+}
+
+/// Returns the column where the text starts and the text with surrounding whitespace removed.
+pub fn trim_with_offset(text: &str, offset: usize) -> (usize, &str) {
+    let trimmed = text.trim_start();
+    (offset + text.len() - trimmed.len(), trimmed.trim_end())
+}
+
+/// Returns the index of the first char outside of char and string literals matching `f`.
+pub fn find_unquoted(text: &str, f: impl Fn(char) -> bool) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else if c == '\'' || c == '"' {
+            quote = Some(c);
+        } else if f(c) {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+/// Removes a trailing `;` comment that is not part of a literal.
+pub fn strip_comment(text: &str) -> &str {
+    match find_unquoted(text, |c| c == ';') {
+        Some(i) => &text[..i],
+        None => text,
+    }
+}
+
+/// Splits the operand list at commas outside of literals and keeps the span of every operand.
+pub fn split_operands(operands: &str, offset: usize) -> Vec<(&str, Span)> {
+    let mut split = Vec::new();
+    let mut start = 0;
+
+    while let Some(i) = find_unquoted(&operands[start..], |c| c == ',') {
+        split.push((start, start + i));
+        start += i + 1;
+    }
+    split.push((start, operands.len()));
+
+    split
+        .into_iter()
+        .map(|(s, e)| {
+            let (column, operand) = trim_with_offset(&operands[s..e], offset + s);
+            (operand, Span::new(column, column + operand.len()))
+        })
+        .collect()
+}
+
+/// Parses a literal enclosed in `quote` and resolves escape sequences.
+pub fn parse_quoted(literal: &str, quote: char) -> Result<Vec<Byte>, String> {
+    let inner = literal
+        .strip_prefix(quote)
+        .and_then(|l| l.strip_suffix(quote))
+        .filter(|_| literal.len() >= 2)
+        .ok_or_else(|| format!("unterminated literal `{}`", literal))?;
+
+    let mut bytes = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+
+        let escaped = chars
+            .next()
+            .ok_or_else(|| format!("unterminated literal `{}`", literal))?;
+        bytes.push(match escaped {
+            'n' => b'\n',
+            't' => b'\t',
+            'r' => b'\r',
+            '0' => 0,
+            '\\' => b'\\',
+            '\'' => b'\'',
+            '"' => b'"',
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                Byte::from_str_radix(&hex, 16)
+                    .map_err(|_| format!("invalid escape sequence `\\x{}`", hex))?
+            }
+            _ => return Err(format!("invalid escape sequence `\\{}`", escaped)),
+        });
+    }
+
+    Ok(bytes)
+}
+
+pub fn parse_operand(operand: &str) -> Result<OperandKind, String> {
+    if operand.is_empty() {
+        Err("expected operand".to_string())
+    } else if let Some(offset) = isa::register_offset(operand) {
+        // any register of the VM's register file
+        Ok(OperandKind::Register(offset))
+    } else if operand.len() > 1
+        && operand.to_uppercase().starts_with('R')
+        && operand[1..].chars().all(|c| c.is_ascii_digit())
+    {
+        Err(format!("invalid register `{}`", operand))
+    } else {
+        parse_expression(operand).map(OperandKind::Value)
+    }
+}
+
+/// Checks that the operand matches the kind the instruction expects at `index`.
+fn check_operand(
+    definition: &isa::Instruction,
+    index: usize,
+    text: &str,
+    operand: &OperandKind,
+) -> Result<(), String> {
+    use isa::OperandKind as Expected;
+
+    let expected = definition.operands[index];
+    let got = match (expected, operand) {
+        (Expected::Register, OperandKind::Register(_)) => return Ok(()),
+        (Expected::Register, OperandKind::Value(Expr::Label(_))) => "label",
+        (Expected::Register, OperandKind::Value(Expr::Symbol(_))) => "symbol",
+        (Expected::Register, OperandKind::Value(_)) => "immediate",
+
+        // the range of status bits is checked once the value is known
+        (Expected::Flag, OperandKind::Value(Expr::Label(_))) => "label",
+
+        (_, OperandKind::Register(_)) => "register",
+        _ => return Ok(()),
+    };
+
+    // articles for the kind names
+    let article = match expected {
+        Expected::Immediate | Expected::Address => "an",
+        _ => "a",
+    };
+
+    Err(format!(
+        "operand {} of {} must be {} {}, got {} {}",
+        index + 1,
+        definition.mnemonic,
+        article,
+        expected.name(),
+        got,
+        text
+    ))
+}
+
+/// Parses a line into a statement. Empty lines and comments yield `None`.
+///
+/// Errors in single operands don't abort the line, so every bad operand gets reported
+/// and the instruction still has its full size for the label addresses that follow.
+pub fn parse_line(line: &SourceLine, diagnostics: &mut Vec<Diagnostic>) -> Option<Statement> {
+    // comments with ; are allowed, also after a statement
+    let (column, text) = trim_with_offset(strip_comment(line.text), 0);
+
+    if text.is_empty() {
+        return None;
+    } else if let Some(label) = text.strip_prefix(':') {
+        let span = Span::new(column, column + text.len());
+        // anything after the name, like an instruction, would become part of it
+        let is_name = label.starts_with(is_identifier_start) && label.chars().all(is_identifier);
+        if !is_name && !is_anonymous(label) {
+            let span = match label.is_empty() {
+                true => span,
+                false => Span::new(column + 1, span.end),
+            };
+            diagnostics.push(line.error(span, "expected a label name".to_string()));
+            return None;
+        }
+        return Some(Statement::Label(label.to_string(), span));
+    }
+
+    let (instruction, operands) = match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], Some(&text[i..])),
+        None => (text, None),
+    };
+    let instruction_span = Span::new(column, column + instruction.len());
+
+    let operands = match operands {
+        Some(operands) => split_operands(operands, column + instruction.len()),
+        None => Vec::new(),
+    };
+
+    if instruction.starts_with('.') {
+        return parse_directive(line, instruction, instruction_span, operands, diagnostics);
+    }
+
+    if is_generic(instruction) {
+        return parse_generic(line, instruction, instruction_span, operands, diagnostics);
+    }
+
+    let definition = match isa::from_mnemonic(instruction) {
+        Some(definition) => definition,
+        None => {
+            diagnostics.push(line.error(
+                instruction_span,
+                format!("unknown instruction `{}`", instruction),
+            ));
+            return None;
+        }
+    };
+
+    // if the operator count of the current instruction is not equal to the number of operands expected, then error
+    if operands.len() != definition.operands.len() {
+        let span = match (operands.first(), operands.last()) {
+            (Some((_, first)), Some((_, last))) => Span::new(first.start, last.end),
+            _ => instruction_span,
+        };
+        diagnostics.push(line.error(
+            span,
+            format!(
+                "{} expects {} operand(s), got {}",
+                definition.mnemonic,
+                definition.operands.len(),
+                operands.len()
+            ),
+        ));
+        return None;
+    }
+
+    let operands = operands
+        .into_iter()
+        .enumerate()
+        .map(|(i, (operand, span))| {
+            let kind = parse_operand(operand)
+                .and_then(|kind| check_operand(definition, i, operand, &kind).map(|_| kind))
+                .unwrap_or_else(|message| {
+                    diagnostics.push(line.error(span, message));
+                    OperandKind::Value(Expr::Number(0))
+                });
+            Operand { kind, span }
+        })
+        .collect();
+
+    Some(Statement::Instruction(Instruction {
+        definition,
+        operands,
+    }))
+}
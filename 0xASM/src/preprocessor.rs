@@ -0,0 +1,1069 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::directives::constant_operands;
+use crate::expression::{apply, apply_unary, parse_expression, Expr};
+use crate::macros::{self, is_name, replace_names, Macro};
+use crate::parser::{parse_quoted, split_operands, strip_comment, trim_with_offset};
+use crate::pseudo::Pseudo;
+use crate::scope::Scope;
+use crate::{Diagnostic, Options, SourceLine, Span, Word};
+
+/// Deepest nesting of macro invocations, anything deeper is most likely a macro invoking itself.
+const MAX_DEPTH: usize = 64;
+
+/// Most repetitions of a `.rept` block, more are most likely a wrong count.
+const MAX_REPETITIONS: Word = 0x10000;
+
+/// Most lines all repetitions together expand to, the counts of nested ones multiply.
+const MAX_REPEATED_LINES: usize = 0x100000;
+
+/// File name of the constants given with `-D` in diagnostics and listings.
+const COMMAND_LINE: &str = "<command line>";
+
+/// Offset of the argument count from `fp` inside a procedure. `CALL` pushes r1 to r8, pc and
+/// the frame size after it, and `fp` points to the free word below them.
+const ARGUMENT_COUNT_OFFSET: Word = 4 * (8 + 2) + 4;
+
+/// A line after preprocessing. It owns its text since macro expansions create new lines.
+#[derive(Clone)]
+pub struct Line {
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    pub text: String,
+    /// Notes on the macro invocations the line was expanded from
+    pub context: Vec<Diagnostic>,
+    /// Part of a macro expansion
+    pub expanded: bool,
+    /// Already handled by the preprocessor, like `.include` and macros, only kept for listings
+    pub listed_only: bool,
+    /// Name of the procedure the line is in, see [`Scope`](crate::scope::Scope)
+    pub procedure: Option<String>,
+}
+
+impl Line {
+    pub fn source(&self) -> SourceLine<'_> {
+        SourceLine {
+            file: &self.file,
+            line: self.line,
+            text: &self.text,
+            context: &self.context,
+        }
+    }
+}
+
+/// Splits off the first word of a line. Returns the word, its span and the rest of the line.
+pub fn first_word(text: &str) -> (&str, Span, &str) {
+    let (column, text) = trim_with_offset(strip_comment(text), 0);
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+
+    (&text[..end], Span::new(column, column + end), &text[end..])
+}
+
+/// Looks up a file of `.include` or `.incbin`, first next to the file containing the
+/// directive, then in the include paths.
+pub fn find_file(from: &str, name: &str, include_paths: &[PathBuf]) -> Option<PathBuf> {
+    let directory = Path::new(from).parent().unwrap_or_else(|| Path::new(""));
+
+    std::iter::once(directory)
+        .chain(include_paths.iter().map(PathBuf::as_path))
+        .map(|directory| directory.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Parses the file name operand of `.include` and `.incbin`.
+pub fn parse_file_name(line: &SourceLine, operand: &str, span: Span) -> Result<String, Diagnostic> {
+    if !operand.starts_with('"') {
+        return Err(line.error(
+            span,
+            format!("expected file name in quotes, got `{}`", operand),
+        ));
+    }
+
+    parse_quoted(operand, '"')
+        .and_then(|name| {
+            String::from_utf8(name).map_err(|_| "file name is not valid UTF-8".to_string())
+        })
+        .map_err(|message| line.error(span, message))
+}
+
+/// Splits a file into lines, `from` is the `.include` line it was included from.
+fn split_lines(source: &str, file: &str, from: Option<&SourceLine>) -> Vec<Line> {
+    let context: Vec<Diagnostic> = from
+        .into_iter()
+        .map(|line| {
+            let (_, span, _) = first_word(line.text);
+            line.note(span, "included from here".to_string())
+        })
+        .collect();
+
+    source
+        .lines()
+        .enumerate()
+        .map(|(n, text)| Line {
+            file: file.to_string(),
+            line: n + 1,
+            text: text.to_string(),
+            context: context.clone(),
+            expanded: false,
+            listed_only: false,
+            procedure: None,
+        })
+        .collect()
+}
+
+/// An open `.if` block.
+struct Condition {
+    /// The `.if` line and the span of the directive, for the error if it's never closed
+    line: Line,
+    span: Span,
+    /// Lines of the current branch are assembled
+    active: bool,
+    /// A branch was already taken, or the whole block is inside a skipped one
+    taken: bool,
+    /// `.else` was seen, no more branches may follow
+    otherwise: bool,
+}
+
+/// A procedure defined with `.proc`.
+pub struct Procedure {
+    pub name: String,
+    arguments: usize,
+    pub locals: usize,
+    /// Arguments and locals, by the names used inside of the procedure
+    pub names: Vec<String>,
+    /// An instruction was seen, locals have to be pushed before anything else
+    started: bool,
+    /// The `.proc` line and the span of the name in it
+    definition: Line,
+    span: Span,
+}
+
+/// A `CALL` line, expanded once every procedure is known.
+struct Call {
+    /// Index of the line in the preprocessed lines
+    index: usize,
+    /// The target as written and its span
+    target: String,
+    span: Span,
+    arguments: Vec<String>,
+}
+
+/// Lines generated by a directive, listed as its expansion.
+fn generated(from: &Line, span: Span, texts: Vec<String>) -> Vec<Line> {
+    let (word, _, _) = first_word(&from.text);
+    let indent = &from.text[..from.text.len() - from.text.trim_start().len()];
+    let mut context = vec![from
+        .source()
+        .note(span, format!("in expansion of `{}`", word))];
+    context.extend(from.context.iter().cloned());
+
+    texts
+        .into_iter()
+        .map(|text| Line {
+            file: from.file.clone(),
+            line: from.line,
+            text: format!("{}{}", indent, text),
+            context: context.clone(),
+            expanded: true,
+            listed_only: false,
+            procedure: from.procedure.clone(),
+        })
+        .collect()
+}
+
+/// Whether `name` can name a procedure, an argument or a local.
+fn is_procedure_name(name: &str) -> bool {
+    is_name(name) && !name.starts_with('.') && isa::register_offset(name).is_none()
+}
+
+struct Preprocessor<'a> {
+    options: &'a Options,
+    /// Files currently being included, to detect include cycles
+    including: Vec<PathBuf>,
+    /// Macros by lowercase name, invocations are case insensitive like instructions
+    macros: HashMap<String, Macro>,
+    /// Number of expansions so far, makes the local labels of every expansion unique
+    expansions: usize,
+    /// Lines expanded from repetitions so far, `None` once there were too many
+    repeated: Option<usize>,
+    /// Values of the constants defined so far, for `.if`
+    constants: HashMap<String, Word>,
+    /// Labels and constants defined so far, for `.ifdef`
+    defined: HashSet<String>,
+    /// Full names of local and anonymous labels, like in the first pass
+    scope: Scope,
+    /// `.struct` being defined and the offset of its next field, if known
+    structure: Option<(String, Option<Word>)>,
+    /// Procedures by name, for the arguments of `CALL`
+    procedures: HashMap<String, Procedure>,
+    /// Name of the procedure being defined
+    procedure: Option<String>,
+    calls: Vec<Call>,
+    pseudo: Pseudo,
+    lines: Vec<Line>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Preprocessor<'a> {
+    /// Keeps a line the preprocessor handled for listings.
+    fn list(&mut self, line: &Line) {
+        self.lines.push(Line {
+            listed_only: true,
+            ..line.clone()
+        });
+    }
+
+    fn process(&mut self, lines: Vec<Line>, depth: usize) {
+        let mut lines = lines.into_iter();
+        // conditions can't continue past the end of a file or an expansion
+        let mut conditions: Vec<Condition> = Vec::new();
+
+        while let Some(line) = lines.next() {
+            let (word, span, rest) = first_word(&line.text);
+            let directive = word.to_lowercase();
+
+            if matches!(
+                directive.as_str(),
+                ".if" | ".ifdef" | ".ifndef" | ".elif" | ".else" | ".endif"
+            ) {
+                self.list(&line);
+                self.condition(&mut conditions, &line, &directive, span, rest);
+            } else if !conditions.iter().all(|condition| condition.active) {
+                self.list(&line);
+            } else if word.eq_ignore_ascii_case(".macro") {
+                self.list(&line);
+                let mut body = Vec::new();
+                let mut closed = false;
+                for body_line in lines.by_ref() {
+                    self.list(&body_line);
+                    let (word, span, _) = first_word(&body_line.text);
+                    if word.eq_ignore_ascii_case(".endm") {
+                        closed = true;
+                        break;
+                    } else if word.eq_ignore_ascii_case(".macro") {
+                        self.diagnostics.push(
+                            body_line.source().error(
+                                span,
+                                "macros can't be defined inside of macros".to_string(),
+                            ),
+                        );
+                    } else {
+                        body.push(body_line);
+                    }
+                }
+
+                if !closed {
+                    self.diagnostics.push(
+                        line.source()
+                            .error(span, "`.macro` without matching `.endm`".to_string()),
+                    );
+                }
+
+                let definition = line.clone();
+                match Macro::parse(definition, span, rest, body) {
+                    Ok(definition) => self.define(definition),
+                    Err(diagnostics) => self.diagnostics.extend(diagnostics),
+                }
+            } else if word.eq_ignore_ascii_case(".include") {
+                self.list(&line);
+                if let Err(diagnostic) = self.include(&line.source(), span, rest, depth) {
+                    self.diagnostics.push(diagnostic);
+                }
+            } else if word.eq_ignore_ascii_case(".endm") {
+                self.diagnostics.push(
+                    line.source()
+                        .error(span, "`.endm` without `.macro`".to_string()),
+                );
+            } else if directive == ".rept" || directive == ".irp" {
+                self.list(&line);
+                if let Some(body) = self.block(&mut lines, &line, span) {
+                    if let Err(diagnostic) =
+                        self.repeat(&line, &directive, span, rest, &body, depth)
+                    {
+                        self.diagnostics.push(diagnostic);
+                    }
+                }
+            } else if directive == ".proc" {
+                self.list(&line);
+                if let Err(diagnostic) = self.open_procedure(&line, span, rest) {
+                    self.diagnostics.push(diagnostic);
+                }
+            } else if directive == ".local" {
+                self.list(&line);
+                if let Err(diagnostic) = self.locals(&line, span, rest) {
+                    self.diagnostics.push(diagnostic);
+                }
+            } else if directive == ".endproc" {
+                self.list(&line);
+                if self.procedure.take().is_none() {
+                    self.diagnostics.push(
+                        line.source()
+                            .error(span, "`.endproc` without `.proc`".to_string()),
+                    );
+                }
+            } else if directive == ".endr" {
+                self.diagnostics.push(
+                    line.source()
+                        .error(span, "`.endr` without `.rept` or `.irp`".to_string()),
+                );
+            } else if self.macros.contains_key(&word.to_lowercase()) {
+                if depth == MAX_DEPTH {
+                    self.diagnostics.push(line.source().error(
+                        span,
+                        format!(
+                            "macro `{}` is nested more than {} levels deep, does it invoke itself?",
+                            word, MAX_DEPTH
+                        ),
+                    ));
+                    continue;
+                }
+
+                self.list(&line);
+                self.expansions += 1;
+                let expansion = self.macros[&word.to_lowercase()].expand(
+                    &line.source(),
+                    span,
+                    split_operands(rest, span.end),
+                    self.expansions,
+                );
+                match expansion {
+                    Ok(expansion) => self.process(expansion, depth + 1),
+                    Err(diagnostic) => self.diagnostics.push(diagnostic),
+                }
+            } else {
+                let line = self.in_procedure(line);
+                self.instruction(line);
+            }
+        }
+
+        for condition in conditions {
+            self.diagnostics.push(condition.line.source().error(
+                condition.span,
+                format!(
+                    "`{}` without matching `.endif`",
+                    first_word(&condition.line.text).0
+                ),
+            ));
+        }
+    }
+
+    /// Adds a line for the assembler.
+    fn emit(&mut self, line: Line) {
+        self.track(&line);
+        self.record_call(&line);
+        self.lines.push(line);
+    }
+
+    /// Adds a line with an instruction, pseudo-instructions are listed with their expansion.
+    fn instruction(&mut self, line: Line) {
+        let (word, span, rest) = first_word(&line.text);
+        if word.is_empty() {
+            return self.emit(line);
+        }
+
+        let mut operands: Vec<&str> = split_operands(rest, span.end)
+            .into_iter()
+            .map(|(operand, _)| operand)
+            .collect();
+        if operands.len() == 1 && operands[0].is_empty() {
+            operands.clear();
+        }
+
+        match self.pseudo.expand(word, &operands) {
+            None => self.emit(line),
+            Some(Ok(texts)) => {
+                self.list(&line);
+                for line in generated(&line, span, texts) {
+                    self.emit(line);
+                }
+            }
+            Some(Err(message)) => {
+                self.list(&line);
+                self.diagnostics.push(line.source().error(span, message));
+            }
+        }
+    }
+
+    /// Handles `.if`, `.ifdef`, `.ifndef`, `.elif`, `.else` and `.endif`.
+    fn condition(
+        &mut self,
+        conditions: &mut Vec<Condition>,
+        line: &Line,
+        directive: &str,
+        span: Span,
+        rest: &str,
+    ) {
+        let source = line.source();
+
+        match directive {
+            ".if" | ".ifdef" | ".ifndef" => {
+                // blocks inside a skipped branch are skipped as a whole, without evaluating them
+                let enclosing = conditions.iter().all(|condition| condition.active);
+                let active = enclosing && self.test(&source, directive, span, rest);
+                conditions.push(Condition {
+                    line: line.clone(),
+                    span,
+                    active,
+                    taken: active || !enclosing,
+                    otherwise: false,
+                });
+            }
+            ".elif" | ".else" => match conditions.last_mut() {
+                None => self
+                    .diagnostics
+                    .push(source.error(span, format!("`{}` without `.if`", directive))),
+                Some(condition) if condition.otherwise => self
+                    .diagnostics
+                    .push(source.error(span, format!("`{}` after `.else`", directive))),
+                Some(condition) => {
+                    condition.active = !condition.taken
+                        && (directive == ".else" || self.test(&source, directive, span, rest));
+                    condition.taken |= condition.active;
+                    condition.otherwise = directive == ".else";
+                }
+            },
+            _ => {
+                if conditions.pop().is_none() {
+                    self.diagnostics
+                        .push(source.error(span, "`.endif` without `.if`".to_string()));
+                }
+            }
+        }
+    }
+
+    /// Evaluates the condition of `.if`, `.elif`, `.ifdef` or `.ifndef`, errors count as false.
+    fn test(&mut self, line: &SourceLine, directive: &str, span: Span, rest: &str) -> bool {
+        let (column, operand) = trim_with_offset(rest, span.end);
+        let operand = operand.trim_end();
+        let operand_span = Span::new(column, column + operand.len());
+
+        let result = if operand.is_empty() {
+            Err(line.error(span, format!("{} expects 1 operand(s), got 0", directive)))
+        } else if directive == ".ifdef" || directive == ".ifndef" {
+            match is_name(operand) {
+                true => {
+                    let defined = self.defined.contains(&self.scope.resolve(operand));
+                    Ok(defined == (directive == ".ifdef"))
+                }
+                false => {
+                    Err(line.error(operand_span, format!("invalid symbol name `{}`", operand)))
+                }
+            }
+        } else {
+            self.evaluate(operand)
+                .map(|value| value != 0)
+                .map_err(|message| line.error(operand_span, message))
+        };
+
+        result.unwrap_or_else(|diagnostic| {
+            self.diagnostics.push(diagnostic);
+            false
+        })
+    }
+
+    /// Evaluates an expression of `.if` or `.rept`, which can only use constants defined before it.
+    fn evaluate(&self, text: &str) -> Result<Word, String> {
+        fn value(constant: &dyn Fn(&str) -> Option<Word>, expr: &Expr) -> Result<Word, String> {
+            match expr {
+                Expr::Number(value) => Ok(*value),
+                Expr::Label(name) | Expr::Symbol(name) => {
+                    constant(name).ok_or_else(|| {
+                        format!(
+                            "`{}` is not a constant defined before this line, only those can be used here",
+                            name
+                        )
+                    })
+                }
+                Expr::SizeOf(name) => Err(format!(
+                    "the size of `{}` is not known before the program is assembled",
+                    name
+                )),
+                Expr::Unary(op, operand) => Ok(apply_unary(*op, value(constant, operand)?)),
+                Expr::Binary(op, lhs, rhs) => {
+                    apply(*op, value(constant, lhs)?, value(constant, rhs)?)
+                }
+            }
+        }
+
+        let constant = |name: &str| self.constants.get(&self.scope.resolve(name)).copied();
+        value(&constant, &parse_expression(text)?)
+    }
+
+    /// Remembers the labels and constants of a line for `.ifdef`, and the values of constants for `.if`.
+    /// Names are qualified like in the first pass, see [`Scope`].
+    fn track(&mut self, line: &Line) {
+        let (word, span, rest) = first_word(&line.text);
+        let directive = word.to_lowercase();
+        let operands = split_operands(rest, span.end);
+
+        // constants defined in terms of labels are only known to the assembler
+        let (name, value) = if let Some(label) = word.strip_prefix(':') {
+            (self.scope.define(label), None)
+        } else if directive == ".equ" || directive == ".define" {
+            let operands = constant_operands(operands);
+            match operands.first() {
+                Some(&(name, _)) => (
+                    self.scope.local(name),
+                    operands
+                        .get(1)
+                        .and_then(|(value, _)| self.evaluate(value).ok()),
+                ),
+                None => return,
+            }
+        } else if directive == ".struct" {
+            self.structure = Some((operands[0].0.to_string(), Some(0)));
+            return;
+        } else if directive == ".field" {
+            let size = match operands.get(1) {
+                Some((size, _)) => self.evaluate(size).ok(),
+                None => Some(4),
+            };
+            match &mut self.structure {
+                Some((structure, offset)) => {
+                    let field = (format!("{}.{}", structure, operands[0].0), *offset);
+                    *offset = offset
+                        .zip(size)
+                        .map(|(offset, size)| offset.wrapping_add(size));
+                    field
+                }
+                None => return,
+            }
+        } else if directive == ".endstruct" {
+            match self.structure.take() {
+                Some((structure, size)) => (format!("{}.size", structure), size),
+                None => return,
+            }
+        } else {
+            return;
+        };
+
+        if let Some(value) = value {
+            self.constants.insert(name.clone(), value);
+        }
+        self.defined.insert(name);
+    }
+
+    /// Starts `.proc name(argument, argument)`, defines its label and the offsets of the
+    /// arguments. The last argument is pushed last, right before the argument count.
+    fn open_procedure(
+        &mut self,
+        line: &Line,
+        directive: Span,
+        rest: &str,
+    ) -> Result<(), Diagnostic> {
+        let source = line.source();
+        if let Some(open) = &self.procedure {
+            return Err(source.error(
+                directive,
+                format!(
+                    "procedures can't be defined inside of procedures, `{}` isn't closed with `.endproc`",
+                    open
+                ),
+            ));
+        }
+
+        let (column, text) = trim_with_offset(rest, directive.end);
+        let (name, arguments) = match text.find('(') {
+            Some(i) if text.ends_with(')') => (
+                text[..i].trim_end(),
+                Some((i, &text[i + 1..text.len() - 1])),
+            ),
+            Some(_) => {
+                return Err(source.error(
+                    Span::new(column, column + text.len()),
+                    "expected `)` at the end of the arguments".to_string(),
+                ))
+            }
+            None => (text, None),
+        };
+        let span = Span::new(column, column + name.len());
+        if name.is_empty() {
+            return Err(source.error(directive, "expected procedure name".to_string()));
+        } else if !is_procedure_name(name) {
+            return Err(source.error(span, format!("invalid procedure name `{}`", name)));
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        if let Some((start, arguments)) = arguments.filter(|(_, a)| !a.trim().is_empty()) {
+            for (argument, span) in split_operands(arguments, column + start + 1) {
+                if !is_procedure_name(argument) {
+                    return Err(source.error(span, format!("invalid argument name `{}`", argument)));
+                } else if names.iter().any(|n| n == argument) {
+                    return Err(
+                        source.error(span, format!("argument `{}` is given twice", argument))
+                    );
+                }
+                names.push(argument.to_string());
+            }
+        }
+
+        let mut texts = vec![format!(":{}", name)];
+        for (i, argument) in names.iter().enumerate() {
+            let offset = ARGUMENT_COUNT_OFFSET + 4 * (names.len() - i) as Word;
+            texts.push(format!(".equ {}.{}, {}", name, argument, offset));
+        }
+        for line in generated(line, span, texts) {
+            self.emit(line);
+        }
+
+        self.procedure = Some(name.to_string());
+        self.procedures.insert(
+            name.to_string(),
+            Procedure {
+                name: name.to_string(),
+                arguments: names.len(),
+                locals: 0,
+                names,
+                started: false,
+                definition: line.clone(),
+                span,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Pushes the locals of `.local name, name`, the first one is at `fp` and every next one
+    /// a word below it.
+    fn locals(&mut self, line: &Line, directive: Span, rest: &str) -> Result<(), Diagnostic> {
+        let source = line.source();
+        let procedure = match &self.procedure {
+            Some(name) => self.procedures.get_mut(name).unwrap(),
+            None => return Err(source.error(directive, "`.local` outside of `.proc`".to_string())),
+        };
+        if procedure.started {
+            return Err(source.error(
+                directive,
+                "locals are pushed first, `.local` has to come before the instructions of the procedure".to_string(),
+            ));
+        }
+
+        let operands = split_operands(rest, directive.end);
+        if operands[0].0.is_empty() {
+            return Err(source.error(
+                directive,
+                ".local expects at least 1 operand(s), got 0".to_string(),
+            ));
+        }
+
+        let mut texts = Vec::new();
+        for (local, span) in operands {
+            if !is_procedure_name(local) {
+                return Err(source.error(span, format!("invalid local name `{}`", local)));
+            } else if procedure.names.iter().any(|n| n == local) {
+                return Err(source.error(
+                    span,
+                    format!(
+                        "`{}` is already an argument or local of `{}`",
+                        local, procedure.name
+                    ),
+                ));
+            }
+
+            let offset = (4 * procedure.locals as Word).wrapping_neg() as i32;
+            texts.push(format!(".equ {}.{}, {}", procedure.name, local, offset));
+            texts.push("PUSH 0".to_string());
+            procedure.names.push(local.to_string());
+            procedure.locals += 1;
+        }
+
+        for line in generated(line, directive, texts) {
+            self.emit(line);
+        }
+
+        Ok(())
+    }
+
+    /// Marks the lines of a procedure, the first pass resolves the names of its arguments
+    /// and locals in them.
+    fn in_procedure(&mut self, mut line: Line) -> Line {
+        let procedures = &mut self.procedures;
+        let procedure = match &self.procedure {
+            Some(name) => procedures.get_mut(name).unwrap(),
+            None => return line,
+        };
+        line.procedure = Some(procedure.name.clone());
+
+        let (word, span, rest) = first_word(&line.text);
+        if !word.is_empty() && !word.starts_with('.') && !word.starts_with(':') {
+            procedure.started = true;
+        }
+        if !word.eq_ignore_ascii_case("call") {
+            return line;
+        }
+
+        // arguments of a call pass the value of an argument or local, not its offset from fp
+        for (operand, operand_span) in split_operands(rest, span.end).into_iter().skip(1) {
+            let mut mentioned = false;
+            replace_names(operand, |name, escaped| {
+                mentioned |= !escaped && procedure.names.iter().any(|n| n == name);
+                None
+            });
+            if mentioned && !procedure.names.iter().any(|name| name == operand) {
+                self.diagnostics.push(line.source().error(
+                    operand_span,
+                    format!(
+                        "arguments and locals of `{}` can only be passed to `CALL` on their own",
+                        procedure.name
+                    ),
+                ));
+            }
+        }
+
+        line
+    }
+
+    /// Remembers a `CALL`, see [`Preprocessor::expand_calls`].
+    fn record_call(&mut self, line: &Line) {
+        let (word, span, rest) = first_word(&line.text);
+        if !word.eq_ignore_ascii_case("call") {
+            return;
+        }
+
+        let operands = split_operands(rest, span.end);
+        let (target, target_span) = operands[0];
+        if !target.is_empty() {
+            self.calls.push(Call {
+                index: self.lines.len(),
+                target: target.to_string(),
+                span: target_span,
+                arguments: operands[1..].iter().map(|(a, _)| a.to_string()).collect(),
+            });
+        }
+    }
+
+    /// Pushes the arguments and their count before every `CALL` of a procedure or with
+    /// arguments, `RET` drops them again.
+    fn expand_calls(&mut self) {
+        // from the last call, so the indices of the earlier ones stay valid
+        while let Some(call) = self.calls.pop() {
+            let procedure = self.procedures.get(call.target.trim_start_matches(':'));
+            if call.arguments.is_empty() && procedure.is_none() {
+                continue;
+            }
+
+            let line = self.lines[call.index].clone();
+            if let Some(procedure) = procedure.filter(|p| p.arguments != call.arguments.len()) {
+                self.diagnostics.push(
+                    line.source()
+                        .error(
+                            call.span,
+                            format!(
+                                "procedure `{}` takes {} argument(s), got {}",
+                                procedure.name,
+                                procedure.arguments,
+                                call.arguments.len()
+                            ),
+                        )
+                        .with_note(
+                            procedure
+                                .definition
+                                .source()
+                                .note(procedure.span, "defined here".to_string()),
+                        ),
+                );
+                continue;
+            }
+
+            let (_, span, _) = first_word(&line.text);
+            let names = line
+                .procedure
+                .as_ref()
+                .map_or(&[][..], |name| &self.procedures[name].names[..]);
+            let mut texts = Vec::new();
+            for argument in &call.arguments {
+                // `PUSH` only takes values and registers, memory is loaded into acc first
+                if names.contains(argument) {
+                    texts.push(format!("MOV [fp + {}], acc", argument));
+                    texts.push("PUSH acc".to_string());
+                } else if argument.starts_with('[') {
+                    texts.push(format!("MOV {}, acc", argument));
+                    texts.push("PUSH acc".to_string());
+                } else {
+                    texts.push(format!("PUSH {}", argument));
+                }
+            }
+            texts.push(format!("PUSH {}", call.arguments.len()));
+            texts.push(format!("CALL {}", call.target));
+
+            let mut lines = vec![Line {
+                listed_only: true,
+                ..line.clone()
+            }];
+            lines.extend(generated(&line, span, texts));
+            self.lines.splice(call.index..call.index + 1, lines);
+        }
+    }
+
+    /// Takes the lines up to the `.endr` closing a `.rept` or `.irp`, nested blocks included.
+    fn block(
+        &mut self,
+        lines: &mut impl Iterator<Item = Line>,
+        start: &Line,
+        span: Span,
+    ) -> Option<Vec<Line>> {
+        let mut body = Vec::new();
+        let mut nesting = 0;
+
+        for line in lines {
+            self.list(&line);
+            let (word, _, _) = first_word(&line.text);
+            if word.eq_ignore_ascii_case(".rept") || word.eq_ignore_ascii_case(".irp") {
+                nesting += 1;
+            } else if word.eq_ignore_ascii_case(".endr") {
+                if nesting == 0 {
+                    return Some(body);
+                }
+                nesting -= 1;
+            }
+            body.push(line);
+        }
+
+        self.diagnostics.push(start.source().error(
+            span,
+            format!("`{}` without matching `.endr`", first_word(&start.text).0),
+        ));
+        None
+    }
+
+    /// Expands `.rept count` or `.irp name, value, value` with the lines of its block.
+    fn repeat(
+        &mut self,
+        line: &Line,
+        directive: &str,
+        span: Span,
+        rest: &str,
+        body: &[Line],
+        depth: usize,
+    ) -> Result<(), Diagnostic> {
+        let source = line.source();
+        let operands = split_operands(rest, span.end);
+        if operands[0].0.is_empty() || (directive == ".rept" && operands.len() != 1) {
+            let count = if directive == ".rept" {
+                "1"
+            } else {
+                "at least 1"
+            };
+            return Err(source.error(
+                span,
+                format!(
+                    "{} expects {} operand(s), got {}",
+                    directive,
+                    count,
+                    operands.len()
+                ),
+            ));
+        } else if depth == MAX_DEPTH {
+            return Err(source.error(
+                span,
+                format!(
+                    "`{}` is nested more than {} levels deep",
+                    directive, MAX_DEPTH
+                ),
+            ));
+        }
+
+        let (operand, operand_span) = operands[0];
+        let (parameter, values) = if directive == ".rept" {
+            let count = self
+                .evaluate(operand)
+                .map_err(|message| source.error(operand_span, message))?;
+            if count > MAX_REPETITIONS {
+                return Err(source.error(
+                    operand_span,
+                    format!(
+                        "`.rept` count {} is more than the limit of {}",
+                        count, MAX_REPETITIONS
+                    ),
+                ));
+            }
+            (None, vec![""; count as usize])
+        } else if is_name(operand) {
+            (
+                Some(operand),
+                operands[1..].iter().map(|(value, _)| *value).collect(),
+            )
+        } else {
+            return Err(source.error(
+                operand_span,
+                format!("invalid parameter name `{}`", operand),
+            ));
+        };
+
+        for (i, value) in values.into_iter().enumerate() {
+            // empty bodies count too, nesting them still takes time
+            let repeated = match self.repeated {
+                Some(repeated) => repeated + body.len().max(1),
+                // already reported by the repetition that went over the limit
+                None => break,
+            };
+            if repeated > MAX_REPEATED_LINES {
+                self.repeated = None;
+                return Err(source.error(
+                    span,
+                    format!(
+                        "repetitions expand to more than {} lines",
+                        MAX_REPEATED_LINES
+                    ),
+                ));
+            }
+            self.repeated = Some(repeated);
+
+            self.expansions += 1;
+            let context =
+                [source.note(span, format!("in repetition {} of `{}`", i + 1, directive))];
+            let lines = macros::repeat(
+                body,
+                parameter.map(|parameter| (parameter, value)),
+                &context,
+                self.expansions,
+            );
+            self.process(lines, depth + 1);
+        }
+
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        line: &SourceLine,
+        directive: Span,
+        rest: &str,
+        depth: usize,
+    ) -> Result<(), Diagnostic> {
+        let operands = split_operands(rest, directive.end);
+        let (operand, span) = operands[0];
+        if operands.len() != 1 || operand.is_empty() {
+            return Err(line.error(
+                directive,
+                format!(".include expects 1 operand(s), got {}", operands.len()),
+            ));
+        }
+
+        let name = parse_file_name(line, operand, span)?;
+        let path = find_file(line.file, &name, &self.options.include_paths).ok_or_else(|| {
+            line.error(
+                span,
+                format!(
+                    "can't find `{}` next to {} or in the include paths",
+                    name, line.file
+                ),
+            )
+        })?;
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if let Some(start) = self.including.iter().position(|p| *p == canonical) {
+            let cycle: Vec<String> = self.including[start..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(line.error(span, format!("include cycle: {}", cycle.join(" -> "))));
+        }
+
+        let source = fs::read_to_string(&path)
+            .map_err(|e| line.error(span, format!("can't read `{}`: {}", path.display(), e)))?;
+        let lines = split_lines(&source, &path.to_string_lossy(), Some(line));
+
+        self.including.push(canonical);
+        self.process(lines, depth);
+        self.including.pop();
+
+        Ok(())
+    }
+
+    fn define(&mut self, definition: Macro) {
+        let name = definition.name.to_lowercase();
+
+        if let Some(previous) = self.macros.get(&name) {
+            self.diagnostics.push(
+                definition
+                    .definition
+                    .source()
+                    .error(
+                        definition.span,
+                        format!("macro `{}` is already defined", definition.name),
+                    )
+                    .with_note(
+                        previous
+                            .definition
+                            .source()
+                            .note(previous.span, "first defined here".to_string()),
+                    ),
+            );
+        } else if let Some(instruction) = isa::from_mnemonic(&name) {
+            self.diagnostics.push(definition.definition.source().error(
+                definition.span,
+                format!(
+                    "macro `{}` has the same name as the instruction {}",
+                    definition.name, instruction.mnemonic
+                ),
+            ));
+        } else {
+            self.macros.insert(name, definition);
+        }
+    }
+}
+
+/// Splits the source into lines, includes files, expands macros, repeat blocks, procedures,
+/// calls and pseudo-instructions and removes the lines of conditions that are false.
+///
+/// Also returns every procedure, sorted by name.
+pub fn preprocess(source: &str, options: &Options) -> (Vec<Line>, Vec<Procedure>, Vec<Diagnostic>) {
+    let mut preprocessor = Preprocessor {
+        options,
+        including: Path::new(&options.file_name)
+            .canonicalize()
+            .into_iter()
+            .collect(),
+        macros: HashMap::new(),
+        expansions: 0,
+        repeated: Some(0),
+        constants: HashMap::new(),
+        defined: HashSet::new(),
+        scope: Scope::default(),
+        structure: None,
+        procedures: HashMap::new(),
+        procedure: None,
+        calls: Vec::new(),
+        pseudo: Pseudo::default(),
+        lines: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    // `-D NAME=value` defines a constant before the first line
+    let mut lines: Vec<Line> = options
+        .defines
+        .iter()
+        .enumerate()
+        .map(|(n, (name, value))| Line {
+            file: COMMAND_LINE.to_string(),
+            line: n + 1,
+            text: format!(".equ {}, {}", name, value),
+            context: Vec::new(),
+            expanded: false,
+            listed_only: false,
+            procedure: None,
+        })
+        .collect();
+    lines.extend(split_lines(source, &options.file_name, None));
+    preprocessor.process(lines, 0);
+
+    if let Some(name) = preprocessor.procedure.take() {
+        let procedure = &preprocessor.procedures[&name];
+        let diagnostic = procedure.definition.source().error(
+            procedure.span,
+            format!("`.proc {}` without matching `.endproc`", name),
+        );
+        preprocessor.diagnostics.push(diagnostic);
+    }
+    preprocessor.expand_calls();
+
+    let mut procedures: Vec<Procedure> = preprocessor.procedures.into_values().collect();
+    procedures.sort_by(|a, b| a.name.cmp(&b.name));
+
+    (preprocessor.lines, procedures, preprocessor.diagnostics)
+}
@@ -0,0 +1,121 @@
+use isa::register_offset;
+
+use crate::expression::is_identifier;
+
+/// Bits of the status register, see `VM::update_sr`
+const ZERO_BIT: u8 = 0;
+const OVERFLOW_BIT: u8 = 1;
+
+/// Registers saved by `PUSHA`, `POPA` restores them in reverse order.
+const SAVED_REGISTERS: &[&str] = &["acc", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8"];
+
+/// Branches comparing acc, with a single operand they compare the operands of `CMP`.
+const COMPARE_BRANCHES: &[&str] = &["BREQ", "BRNQ", "BRLT", "BRGT", "BRLTE", "BRGTE"];
+
+/// Expands pseudo-instructions into the instructions they stand for.
+#[derive(Default)]
+pub struct Pseudo {
+    /// Second operand of the `CMP` right before the current line
+    compared: Option<String>,
+}
+
+/// `LEA source, register` loads the address of the source instead of the value at it.
+fn lea(source: &str, destination: &str) -> Vec<String> {
+    let inner = match source.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        Some(inner) => inner.trim(),
+        None => return vec![format!("MOVR {}, {}", source, destination)],
+    };
+
+    // `[register + offset]` adds the offset to the register
+    let end = inner.find(|c| !is_identifier(c)).unwrap_or(inner.len());
+    let (register, offset) = (&inner[..end], inner[end..].trim_start());
+    match (register_offset(register), offset.chars().next()) {
+        (Some(_), None) => vec![format!("MOVRR {}, {}", register, destination)],
+        (Some(_), Some(sign @ '+')) | (Some(_), Some(sign @ '-')) => {
+            let offset = offset[1..].trim();
+            let offset = match sign {
+                '+' => offset.to_string(),
+                _ => format!("-({})", offset),
+            };
+            vec![
+                format!("ADD {}, {}", register, offset),
+                format!("MOVRR acc, {}", destination),
+            ]
+        }
+        _ => vec![format!("MOVR {}, {}", inner, destination)],
+    }
+}
+
+impl Pseudo {
+    /// The instructions a line stands for, `None` if it's not a pseudo-instruction. Every
+    /// line with an instruction or label has to be passed, a branch only uses the operands of
+    /// a `CMP` directly before it.
+    pub fn expand(
+        &mut self,
+        mnemonic: &str,
+        operands: &[&str],
+    ) -> Option<Result<Vec<String>, String>> {
+        let mnemonic = mnemonic.to_uppercase();
+        let compared = self.compared.take();
+        let expect = |count: usize| match operands.len() == count {
+            true => Ok(()),
+            false => Err(format!(
+                "{} expects {} operand(s), got {}",
+                mnemonic,
+                count,
+                operands.len()
+            )),
+        };
+        let branch = |flag: u8, set: bool| {
+            expect(1).map(|_| {
+                let instruction = if set { "BRBS" } else { "BRBC" };
+                vec![format!("{} {}, {}", instruction, flag, operands[0])]
+            })
+        };
+
+        Some(match mnemonic.as_str() {
+            "CLR" => expect(1).map(|_| vec![format!("MOVR 0, {}", operands[0])]),
+            "NEG" => expect(1).map(|_| {
+                vec![
+                    format!("SUBWR 0, {}", operands[0]),
+                    format!("MOVRR acc, {}", operands[0]),
+                ]
+            }),
+            "BRZ" => branch(ZERO_BIT, true),
+            "BRNZ" => branch(ZERO_BIT, false),
+            "BRC" => branch(OVERFLOW_BIT, true),
+            "BRNC" => branch(OVERFLOW_BIT, false),
+            "LEA" => expect(2).map(|_| lea(operands[0], operands[1])),
+            "PUSHA" => expect(0).map(|_| {
+                SAVED_REGISTERS
+                    .iter()
+                    .map(|register| format!("PUSHR {}", register))
+                    .collect()
+            }),
+            "POPA" => expect(0).map(|_| {
+                SAVED_REGISTERS
+                    .iter()
+                    .rev()
+                    .map(|register| format!("POP {}", register))
+                    .collect()
+            }),
+            "CMP" => expect(2).map(|_| {
+                self.compared = Some(operands[1].to_string());
+                vec![format!("MOV {}, acc", operands[0])]
+            }),
+            branch if operands.len() == 1 && COMPARE_BRANCHES.contains(&branch) => match compared {
+                Some(value) => {
+                    // more branches on the same comparison can follow
+                    let expansion = vec![format!("{} {}, {}", branch, value, operands[0])];
+                    self.compared = Some(value);
+                    Ok(expansion)
+                }
+                None => Err(format!(
+                    "{} with one operand compares the operands of CMP, which has to come right before it",
+                    branch
+                )),
+            },
+            _ => return None,
+        })
+    }
+}
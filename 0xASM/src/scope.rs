@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use crate::directives::Data;
+use crate::expression::{is_anonymous, is_anonymous_reference, Expr};
+use crate::parser::{Operand, OperandKind, Statement};
+use crate::preprocessor::Procedure;
+
+/// Turns local and anonymous label names into unique names while the first pass goes
+/// through the lines in order.
+///
+/// Local labels starting with `.` belong to the last global label, `.loop` after `:print`
+/// is `print.loop`. Anonymous labels like `:1` can be defined any number of times, `1f`
+/// references the next and `1b` the previous one. The n-th `:1` is named `1@n`. Inside of
+/// a procedure its arguments and locals are referenced by their short names, `a` inside
+/// of `.proc add(a, b)` is `add.a`.
+#[derive(Default)]
+pub struct Scope {
+    /// Last global label
+    global: Option<String>,
+    /// Number of definitions so far, by anonymous label
+    anonymous: HashMap<String, usize>,
+    /// Names of the arguments and locals, by procedure
+    procedures: HashMap<String, Vec<String>>,
+    /// Procedure of the current line
+    procedure: Option<String>,
+}
+
+impl Scope {
+    pub fn new(procedures: &[Procedure]) -> Self {
+        Scope {
+            procedures: procedures
+                .iter()
+                .map(|procedure| (procedure.name.clone(), procedure.names.clone()))
+                .collect(),
+            ..Scope::default()
+        }
+    }
+
+    pub fn local(&self, name: &str) -> String {
+        match &self.global {
+            Some(global) if name.starts_with('.') => format!("{}{}", global, name),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Full name of a label definition. Global labels start a new scope, labels local to
+    /// a macro expansion don't.
+    pub fn define(&mut self, name: &str) -> String {
+        if is_anonymous(name) {
+            let count = self.anonymous.entry(name.to_string()).or_insert(0);
+            *count += 1;
+            format!("{}@{}", name, *count - 1)
+        } else if name.starts_with('.') {
+            self.local(name)
+        } else {
+            if !name.contains('@') {
+                self.global = Some(name.to_string());
+            }
+            name.to_string()
+        }
+    }
+
+    /// Full name of a referenced label. An anonymous label without a previous definition
+    /// keeps its name, so it is reported as undefined.
+    pub fn resolve(&self, name: &str) -> String {
+        if let Some(procedure) = &self.procedure {
+            if self.procedures[procedure].iter().any(|n| n == name) {
+                return format!("{}.{}", procedure, name);
+            }
+        }
+        if !is_anonymous_reference(name) {
+            return self.local(name);
+        }
+
+        let (number, direction) = name.split_at(name.len() - 1);
+        let count = self.anonymous.get(number).copied().unwrap_or(0);
+        match (direction, count) {
+            ("f", _) => format!("{}@{}", number, count),
+            (_, 0) => name.to_string(),
+            _ => format!("{}@{}", number, count - 1),
+        }
+    }
+
+    fn expression(&self, expr: &mut Expr) {
+        match expr {
+            Expr::Label(name) | Expr::Symbol(name) | Expr::SizeOf(name) => {
+                *name = self.resolve(name)
+            }
+            Expr::Unary(_, operand) => self.expression(operand),
+            Expr::Binary(_, lhs, rhs) => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expr::Number(_) => {}
+        }
+    }
+
+    fn operand(&self, operand: &mut Operand) {
+        if let OperandKind::Value(expr) = &mut operand.kind {
+            self.expression(expr);
+        }
+    }
+
+    /// Replaces every label name in the statement with its full name, `procedure` is the
+    /// procedure the statement is in.
+    pub fn qualify(&mut self, statement: &mut Statement, procedure: Option<&str>) {
+        self.procedure = procedure.map(str::to_string);
+        match statement {
+            Statement::Label(name, _) => *name = self.define(name),
+            Statement::Constant(name, _, value) => {
+                *name = self.local(name);
+                self.operand(value);
+            }
+            Statement::Instruction(instruction) => instruction
+                .operands
+                .iter_mut()
+                .for_each(|o| self.operand(o)),
+            Statement::Data(Data::Values(_, values)) => {
+                values.iter_mut().for_each(|o| self.operand(o))
+            }
+            Statement::Data(Data::Fill(count, value)) => {
+                self.operand(count);
+                self.operand(value);
+            }
+            Statement::Data(Data::Align(operand)) | Statement::Data(Data::Org(operand)) => {
+                self.operand(operand)
+            }
+            Statement::Global(names) | Statement::Extern(names) => {
+                for (name, _) in names {
+                    *name = self.local(name);
+                }
+            }
+            Statement::Field(_, _, size) => self.operand(size),
+            Statement::Data(_)
+            | Statement::Section(..)
+            | Statement::Struct(..)
+            | Statement::EndStruct => {}
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use crate::expression::{apply, apply_unary, BinaryOp, Expr};
+use crate::{DefinedSymbol, Diagnostic, SourceLine, Span, Word};
+
+/// What an address in an object file is relative to, the linker resolves it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// Start of a section of the same object file, by index
+    Section(usize),
+    Extern(String),
+}
+
+/// A value that may still be relative to a base only known after linking.
+pub struct Relocatable {
+    pub value: Word,
+    pub base: Option<Base>,
+}
+
+impl Relocatable {
+    pub fn absolute(value: Word) -> Self {
+        Relocatable { value, base: None }
+    }
+
+    /// The plain number, `line` and `span` locate the value for diagnostics.
+    pub fn into_absolute(self, line: &SourceLine, span: Span) -> Result<Word, Diagnostic> {
+        match self.base {
+            None => Ok(self.value),
+            Some(Base::Extern(name)) => Err(line.error(
+                span,
+                format!(
+                    "`{}` is an external symbol, assemble with -c and link the object files",
+                    name
+                ),
+            )),
+            Some(Base::Section(_)) => Err(line.error(
+                span,
+                "the value depends on an address that is only known after linking".to_string(),
+            )),
+        }
+    }
+}
+
+pub enum Value {
+    /// Offset of a label inside its section, the address is known once the section is placed
+    Address(usize, Word),
+    /// Declared with `.extern`, defined by another object file
+    Extern,
+    /// Expression of a `.equ` constant and its span, evaluated when the constant is used
+    Constant(Expr, Span),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Address(..) => "label",
+            Value::Constant(..) => "constant",
+            Value::Extern => "external symbol",
+        }
+    }
+}
+
+pub struct Symbol<'a> {
+    pub value: Value,
+    /// Bytes up to the next label, known once the label is closed
+    pub size: Option<Word>,
+    /// Where the symbol was defined
+    pub line: SourceLine<'a>,
+    pub span: Span,
+    pub used: bool,
+    /// Exported to other object files with `.global`
+    pub global: bool,
+}
+
+#[derive(Default)]
+pub struct SymbolTable<'a> {
+    symbols: HashMap<String, Symbol<'a>>,
+    /// Start addresses of the sections, by index
+    sections: Vec<Option<Word>>,
+    /// The last label defined, its size ends at the next label
+    open: Option<String>,
+    /// Constants currently being evaluated, to catch definitions that refer to themselves
+    evaluating: Vec<String>,
+    /// Unplaced labels evaluate to offsets relative to their section, for object files
+    pub relocatable: bool,
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Defines a label or constant. Redefinitions are reported with both locations and keep the first definition.
+    pub fn define(
+        &mut self,
+        name: &str,
+        value: Value,
+        line: SourceLine<'a>,
+        span: Span,
+    ) -> Result<(), Diagnostic> {
+        if let Some(previous) = self.symbols.get(name) {
+            return Err(line
+                .error(
+                    span,
+                    format!(
+                        "{} `{}` is already defined at {}:{}",
+                        value.kind(),
+                        name,
+                        previous.line.file,
+                        previous.line.line
+                    ),
+                )
+                .with_note(
+                    previous
+                        .line
+                        .note(previous.span, "first defined here".to_string()),
+                ));
+        }
+
+        if let Value::Address(_, offset) = value {
+            self.close(offset);
+            self.open = Some(name.to_string());
+        }
+
+        self.symbols.insert(
+            name.to_string(),
+            Symbol {
+                value,
+                size: None,
+                line,
+                span,
+                used: false,
+                global: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Ends the last label at `offset` inside its section, which sets its size.
+    pub fn close(&mut self, offset: Word) {
+        if let Some(symbol) = self
+            .open
+            .take()
+            .and_then(|name| self.symbols.get_mut(&name))
+        {
+            if let Value::Address(_, start) = symbol.value {
+                symbol.size = Some(offset - start);
+            }
+        }
+    }
+
+    /// Sets the start address of a section, which resolves the addresses of its labels.
+    pub fn place(&mut self, section: usize, start: Word) {
+        if self.sections.len() <= section {
+            self.sections.resize(section + 1, None);
+        }
+        self.sections[section] = Some(start);
+    }
+
+    /// Looks up a symbol and marks it as used. Unknown symbols get a "did you mean" hint.
+    fn lookup(
+        &mut self,
+        name: &str,
+        kind: &str,
+        line: &SourceLine,
+        span: Span,
+    ) -> Result<&mut Symbol<'a>, Diagnostic> {
+        if !self.symbols.contains_key(name) && name.starts_with(|c: char| c.is_ascii_digit()) {
+            // anonymous labels, see `Scope`
+            let (number, direction) = match name.strip_suffix('b') {
+                Some(number) => (number, "before"),
+                None => (name.split('@').next().unwrap_or(name), "after"),
+            };
+            return Err(line.error(
+                span,
+                format!("no anonymous label `{}` {} this line", number, direction),
+            ));
+        } else if !self.symbols.contains_key(name) {
+            let mut message = format!("undefined {} `{}`", kind, name);
+            if let Some(suggestion) = self.suggest(name) {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+            return Err(line.error(span, message));
+        }
+
+        let symbol = self.symbols.get_mut(name).unwrap();
+        symbol.used = true;
+        Ok(symbol)
+    }
+
+    fn resolve(
+        &mut self,
+        name: &str,
+        kind: &str,
+        line: &SourceLine,
+        span: Span,
+    ) -> Result<Relocatable, Diagnostic> {
+        let symbol = self.lookup(name, kind, line, span)?;
+
+        let (expr, definition, value_span) = match &symbol.value {
+            Value::Address(section, offset) => {
+                let (section, offset) = (*section, *offset);
+                return match self.sections.get(section).copied().flatten() {
+                    // sections that don't fit in the address space are already reported
+                    Some(start) => Ok(Relocatable::absolute(start.wrapping_add(offset))),
+                    // the linker places the section
+                    None if self.relocatable => Ok(Relocatable {
+                        value: offset,
+                        base: Some(Base::Section(section)),
+                    }),
+                    None => Err(line.error(
+                        span,
+                        format!(
+                            "the address of `{}` is not known before the sections are placed",
+                            name
+                        ),
+                    )),
+                };
+            }
+            Value::Extern => {
+                return Ok(Relocatable {
+                    value: 0,
+                    base: Some(Base::Extern(name.to_string())),
+                })
+            }
+            Value::Constant(expr, value_span) => (expr.clone(), symbol.line, *value_span),
+        };
+
+        if self.evaluating.iter().any(|n| n == name) {
+            return Err(line.error(
+                span,
+                format!("constant `{}` is defined in terms of itself", name),
+            ));
+        }
+
+        self.evaluating.push(name.to_string());
+        let value = self.evaluate_relocatable(&expr, &definition, value_span);
+        self.evaluating.pop();
+
+        value
+    }
+
+    /// Evaluates an expression that has to be a plain number, `line` and `span` locate it for diagnostics.
+    pub fn evaluate(
+        &mut self,
+        expr: &Expr,
+        line: &SourceLine,
+        span: Span,
+    ) -> Result<Word, Diagnostic> {
+        self.evaluate_relocatable(expr, line, span)?
+            .into_absolute(line, span)
+    }
+
+    /// Evaluates an expression that may depend on addresses only known after linking.
+    pub fn evaluate_relocatable(
+        &mut self,
+        expr: &Expr,
+        line: &SourceLine,
+        span: Span,
+    ) -> Result<Relocatable, Diagnostic> {
+        match expr {
+            Expr::Number(value) => Ok(Relocatable::absolute(*value)),
+            Expr::Label(name) => self.resolve(name, "label", line, span),
+            Expr::Symbol(name) => self.resolve(name, "symbol", line, span),
+            Expr::SizeOf(name) => match self.lookup(name, "label", line, span)?.size {
+                Some(size) => Ok(Relocatable::absolute(size)),
+                None => Err(line.error(span, format!("the size of `{}` is not known yet", name))),
+            },
+            Expr::Unary(op, operand) => {
+                let value = self.evaluate(operand, line, span)?;
+                Ok(Relocatable::absolute(apply_unary(*op, value)))
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.evaluate_relocatable(lhs, line, span)?;
+                let rhs = self.evaluate_relocatable(rhs, line, span)?;
+                let value = apply(*op, lhs.value, rhs.value)
+                    .map_err(|message| line.error(span, message))?;
+
+                // only offsets can be added to addresses the linker decides, and
+                // the distance of two labels in the same section is a number
+                let base = match (op, lhs.base, rhs.base) {
+                    (_, None, None) => None,
+                    (BinaryOp::Add, Some(base), None)
+                    | (BinaryOp::Add, None, Some(base))
+                    | (BinaryOp::Sub, Some(base), None) => Some(base),
+                    (BinaryOp::Sub, Some(a), Some(b)) if a == b => None,
+                    _ => {
+                        return Err(line.error(
+                            span,
+                            "only numbers can be added to or subtracted from addresses that are only known after linking".to_string(),
+                        ))
+                    }
+                };
+
+                Ok(Relocatable { value, base })
+            }
+        }
+    }
+
+    /// Exports a label or constant with `.global`.
+    pub fn export(&mut self, name: &str, line: &SourceLine, span: Span) -> Result<(), Diagnostic> {
+        let symbol = self.lookup(name, "symbol", line, span)?;
+        if let Value::Extern = symbol.value {
+            return Err(line.error(
+                span,
+                format!("`{}` is declared with .extern, it can't be .global", name),
+            ));
+        }
+
+        symbol.global = true;
+        Ok(())
+    }
+
+    /// Every symbol, for the symbols of an object file.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Symbol<'a>)> {
+        self.symbols.iter()
+    }
+
+    /// Counts a label as used without a reference in the source, like the entry point.
+    pub fn mark_used(&mut self, name: &str) {
+        if let Some(symbol) = self.symbols.get_mut(name) {
+            symbol.used = true;
+        }
+    }
+
+    /// Warnings for every named label that was never referenced, in source order.
+    pub fn unused(&self) -> Vec<Diagnostic> {
+        let mut unused: Vec<(&String, &Symbol)> = self
+            .symbols
+            .iter()
+            .filter(|(name, s)| {
+                !s.used
+                    && !s.global
+                    && matches!(s.value, Value::Address(..))
+                    && !name.starts_with(|c: char| c.is_ascii_digit())
+            })
+            .collect();
+        unused.sort_by_key(|(_, s)| (s.line.file, s.line.line, s.span.start));
+
+        unused
+            .into_iter()
+            .map(|(name, s)| {
+                s.line
+                    .warning(s.span, format!("label `{}` is never used", name))
+            })
+            .collect()
+    }
+
+    /// Values of the labels and constants, sorted by value. Anonymous labels, external
+    /// symbols and constants that can't be evaluated are left out.
+    pub fn values(&mut self) -> Vec<DefinedSymbol> {
+        let names: Vec<String> = self
+            .symbols
+            .keys()
+            .filter(|name| !name.starts_with(|c: char| c.is_ascii_digit()))
+            .cloned()
+            .collect();
+
+        let mut values: Vec<DefinedSymbol> = names
+            .into_iter()
+            .filter_map(|name| {
+                let symbol = &self.symbols[&name];
+                let (line, span) = (symbol.line, symbol.span);
+                let constant = match symbol.value {
+                    Value::Address(..) => false,
+                    Value::Constant(..) => true,
+                    Value::Extern => return None,
+                };
+
+                let value = self
+                    .resolve(&name, "symbol", &line, span)
+                    .and_then(|value| value.into_absolute(&line, span))
+                    .ok()?;
+                Some(DefinedSymbol {
+                    name,
+                    value,
+                    constant,
+                })
+            })
+            .collect();
+        values.sort_by(|a, b| (a.value, &a.name).cmp(&(b.value, &b.name)));
+
+        values
+    }
+
+    /// Finds the closest known symbol, if any is close enough to be a typo.
+    fn suggest(&self, name: &str) -> Option<&str> {
+        let max_distance = (name.chars().count() / 3).max(1);
+
+        self.symbols
+            .keys()
+            .map(|known| (edit_distance(name, known), known))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min()
+            .map(|(_, known)| known.as_str())
+    }
+}
+
+/// Levenshtein distance between two strings, case insensitive.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
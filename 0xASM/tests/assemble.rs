@@ -0,0 +1,23 @@
+use asm::assemble;
+
+#[test]
+fn resolves_forward_and_backward_labels() {
+    let image = assemble(":start\nJMP :end\nNOP\n:end\nJMP :start").unwrap();
+
+    assert_eq!(image, vec![0x01, 0x06, 0, 0, 0, 0x00, 0x01, 0, 0, 0, 0]);
+}
+
+#[test]
+fn status_bit_is_encoded_as_one_byte() {
+    let image = assemble(":loop\nBRBS 0x1, :loop").unwrap();
+
+    assert_eq!(image, vec![isa::opcodes::BRBS, 0x01, 0, 0, 0, 0]);
+    assert_eq!(isa::decode(&image).unwrap().operands, vec![1, 0]);
+}
+
+#[test]
+fn comments_after_statements() {
+    let image = assemble("; program\n:start ; entry\n    JMP :start ; forever\n").unwrap();
+
+    assert_eq!(image, vec![0x01, 0, 0, 0, 0]);
+}
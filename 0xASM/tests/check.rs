@@ -0,0 +1,185 @@
+use asm::{assemble_with, check, Options};
+
+fn warnings(source: &str) -> Vec<(usize, String)> {
+    let output = assemble_with(source, &Options::default());
+    assert!(!output.has_errors());
+    check(&output)
+        .into_iter()
+        .map(|warning| (warning.line, warning.message))
+        .collect()
+}
+
+#[test]
+fn balanced_programs_have_no_warnings() {
+    let source = "
+        CALL :sum, 1, 2
+        MOVR 3, r1
+    :loop
+        PUSHR r1
+        POP r2
+        DEC r1
+        BRNQ 0, :loop
+        HALT
+    .proc sum(a, b)
+    .local total
+        MOV [fp + a], r1
+        MOV [fp + b], r2
+        ADD r1, r2
+        RET
+    .endproc
+    ";
+
+    assert_eq!(warnings(source), vec![]);
+}
+
+#[test]
+fn unreachable_code() {
+    let source = "JMP :end\nNOP\nNOP\n:end\nHALT\nMOVR 1, r1";
+    let output = assemble_with(source, &Options::default());
+    let diagnostics = check(&output);
+
+    assert_eq!(
+        warnings(source),
+        vec![
+            (2, "unreachable code".to_string()),
+            (6, "unreachable code".to_string())
+        ]
+    );
+    assert_eq!(
+        diagnostics[1].notes[0].message,
+        "execution doesn't continue after this `HALT`"
+    );
+    assert_eq!(diagnostics[1].notes[0].line, 5);
+}
+
+#[test]
+fn execution_leaving_the_code() {
+    assert_eq!(
+        warnings("MOVR 1, r1\nINC r1"),
+        vec![(
+            2,
+            "execution continues past the end of the image".to_string()
+        )]
+    );
+    assert_eq!(
+        warnings("NOP\n.word 0"),
+        vec![(1, "execution continues into data".to_string())]
+    );
+}
+
+#[test]
+fn unbalanced_stacks() {
+    assert_eq!(
+        warnings(":loop\nPUSHR r1\nDEC r1\nBRNQ 0, :loop\nHALT"),
+        vec![(
+            4,
+            "unbalanced stack, `loop` is reached with 1 word(s) pushed here and 0 on another path"
+                .to_string()
+        )]
+    );
+    assert_eq!(
+        warnings("POP r1\nHALT"),
+        vec![(
+            1,
+            "`POP` with nothing pushed, the stack underflows".to_string()
+        )]
+    );
+
+    let source = "
+        CALL :f, 0
+        CALL :g, 0
+        HALT
+    :f
+        PUSHR r1
+        RET
+    :g
+        POP r1
+        RET
+    ";
+    assert_eq!(
+        warnings(source),
+        vec![
+            (
+                7,
+                "unbalanced stack, 1 word(s) pushed in `f` are never popped".to_string()
+            ),
+            (
+                9,
+                "`POP` below the frame of `g`, it takes a word saved by `CALL`".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn calls_and_returns() {
+    assert_eq!(
+        warnings("RET"),
+        vec![(1, "`RET` is reachable without a `CALL`".to_string())]
+    );
+    assert_eq!(
+        warnings("CALL :f\nHALT\n:f\nRET"),
+        vec![(
+            1,
+            "no argument count is pushed right before the call, `RET` pops a word it doesn't own"
+                .to_string()
+        )]
+    );
+    assert_eq!(
+        warnings("PUSH 2\nCALL :f\nHALT\n:f\nRET"),
+        vec![(
+            2,
+            "`RET` drops 2 argument(s) and their count, but only 1 word(s) are pushed".to_string()
+        )]
+    );
+}
+
+#[test]
+fn jump_targets() {
+    let source = "
+        BREQ 1, :table
+        JMP 0x03
+        JMP 0x1000
+        HALT
+    :table
+        .word 1
+    ";
+    let output = assemble_with(source, &Options::default());
+    let diagnostics = check(&output);
+
+    assert_eq!(
+        warnings(source),
+        vec![
+            (2, "branch target `table` is inside data".to_string()),
+            (
+                3,
+                "jump target 0x00000003 is in the middle of an instruction".to_string()
+            ),
+            (
+                4,
+                "jump target 0x00001000 is outside of the image".to_string()
+            ),
+            (4, "unreachable code".to_string()),
+        ]
+    );
+    assert_eq!(diagnostics[0].notes[0].line, 7);
+    assert_eq!(
+        diagnostics[1].notes[0].message,
+        "instruction starts at 0x00000000"
+    );
+}
+
+#[test]
+fn only_procedures_have_locals() {
+    assert_eq!(
+        warnings("PUSH 0\nCALL :f\nHALT\n:f\nPUSH 5\nPOP r1\nRET"),
+        vec![]
+    );
+    assert_eq!(
+        warnings("CALL :f\nHALT\n.proc f\nPUSH 5\nRET\n.endproc"),
+        vec![(
+            5,
+            "unbalanced stack, 1 word(s) pushed in `f` are never popped".to_string()
+        )]
+    );
+}
@@ -0,0 +1,210 @@
+use asm::{assemble, assemble_with, Options};
+
+fn with_defines(source: &str, defines: &[(&str, &str)]) -> Result<Vec<u8>, String> {
+    let options = Options {
+        defines: defines
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        ..Options::default()
+    };
+
+    assemble_with(source, &options)
+        .into_result()
+        .map_err(|diagnostics| diagnostics[0].message.clone())
+}
+
+#[test]
+fn if_elif_else_on_constants() {
+    let source = "
+        .equ WIDTH, 80
+        .if WIDTH == 40
+            .byte 1
+        .elif WIDTH == 80
+            .byte 2
+        .else
+            .byte 3
+        .endif
+        .if WIDTH > 100
+            .byte 4
+        .else
+            .byte 5
+        .endif
+    ";
+
+    assert_eq!(assemble(source), Ok(vec![2, 5]));
+}
+
+#[test]
+fn nested_blocks_in_skipped_branches_are_skipped() {
+    let source = "
+        .if 0
+            .if UNKNOWN
+                .byte 1
+            .else
+                .byte 2
+            .endif
+        .else
+            .byte 3
+        .endif
+    ";
+
+    assert_eq!(assemble(source), Ok(vec![3]));
+}
+
+#[test]
+fn ifdef_and_command_line_defines() {
+    let source = "
+        .ifdef HARD_DRIVE
+            .byte HARD_DRIVE
+        .endif
+        .ifndef SCREEN_WIDTH
+            .equ SCREEN_WIDTH, 80
+        .endif
+        .if SCREEN_WIDTH == 40
+            .byte 40
+        .endif
+        :start
+        .ifdef start
+            .byte 0xFF
+        .endif
+    ";
+
+    assert_eq!(with_defines(source, &[]), Ok(vec![0xFF]));
+    assert_eq!(
+        with_defines(source, &[("HARD_DRIVE", "2"), ("SCREEN_WIDTH", "40")]),
+        Ok(vec![2, 40, 0xFF])
+    );
+}
+
+#[test]
+fn rept_and_irp() {
+    let source = "
+        .equ COUNT, 3
+        .rept COUNT
+            .byte 0xAA
+        .endr
+        .irp value, 1, 2 * 2, 9
+            .rept 2
+                .byte \\value
+            .endr
+        .endr
+    ";
+
+    assert_eq!(
+        assemble(source),
+        Ok(vec![0xAA, 0xAA, 0xAA, 1, 1, 4, 4, 9, 9])
+    );
+}
+
+#[test]
+fn labels_are_unique_per_repetition() {
+    let source = "
+        .rept 2
+        :loop
+            DEC r1
+            BRNQ 0x0, :loop
+        .endr
+    ";
+    let image = assemble(source).unwrap();
+
+    // the second copy jumps back to its own loop
+    assert_eq!(&image[10..14], &0u32.to_le_bytes());
+    assert_eq!(&image[24..28], &14u32.to_le_bytes());
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        assemble(".if 1\n.byte 1").unwrap_err()[0].message,
+        "`.if` without matching `.endif`"
+    );
+    assert_eq!(
+        assemble(".else").unwrap_err()[0].message,
+        "`.else` without `.if`"
+    );
+    assert_eq!(
+        assemble(".if 1\n.else\n.elif 1\n.endif").unwrap_err()[0].message,
+        "`.elif` after `.else`"
+    );
+    assert_eq!(
+        assemble(".if end > 0\n.endif\n:end").unwrap_err()[0].message,
+        "`end` is not a constant defined before this line, only those can be used here"
+    );
+    assert_eq!(
+        assemble(".rept 2\nNOP").unwrap_err()[0].message,
+        "`.rept` without matching `.endr`"
+    );
+    assert_eq!(
+        assemble(".endr").unwrap_err()[0].message,
+        "`.endr` without `.rept` or `.irp`"
+    );
+    let options = Options {
+        defines: vec![("SIZE".to_string(), String::new())],
+        ..Options::default()
+    };
+    let diagnostics = assemble_with("NOP", &options).diagnostics;
+    assert_eq!(diagnostics[0].message, "expected operand");
+    assert_eq!(diagnostics[0].file, "<command line>");
+}
+
+#[test]
+fn nested_repetitions_are_limited() {
+    let diagnostics = assemble(".rept 0x10000\n.rept 0x10000\n.endr\n.endr").unwrap_err();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        "repetitions expand to more than 1048576 lines"
+    );
+    assert_eq!(diagnostics[0].line, 2);
+}
+
+#[test]
+fn comparisons_are_signed() {
+    let source = ".if OFFSET < 0\n.byte 1\n.else\n.byte 2\n.endif";
+
+    assert_eq!(with_defines(source, &[("OFFSET", "-1")]), Ok(vec![1]));
+    assert_eq!(with_defines(source, &[("OFFSET", "1")]), Ok(vec![2]));
+}
+
+#[test]
+fn ifdef_qualifies_local_labels_and_struct_fields() {
+    let source = "
+        :main
+        :.loop
+        .ifdef .loop
+            .byte 1
+        .endif
+        .ifdef main.loop
+            .byte 2
+        .endif
+        .equ .count, 3
+        .if .count == 3
+            .byte 3
+        .endif
+        :other
+        .ifdef .loop
+            .byte 0xEE
+        .endif
+        HALT
+    ";
+    assert_eq!(assemble(source), Ok(vec![1, 2, 3, 0xFF]));
+
+    let source = "
+        .struct S
+        .field a, 4
+        .field b, 2
+        .endstruct
+        .ifdef S.a
+            .byte S.b
+        .endif
+        .if S.size == 6
+            .byte 6
+        .endif
+        .ifdef S.c
+            .byte 0xFF
+        .endif
+    ";
+    assert_eq!(assemble(source), Ok(vec![4, 6]));
+}
@@ -0,0 +1,46 @@
+use asm::assemble;
+
+#[test]
+fn values_are_little_endian() {
+    let image = assemble(".byte 0x1, 'a'\n.half 0x1234\n.word 0xAABBCCDD").unwrap();
+
+    assert_eq!(image, vec![0x01, b'a', 0x34, 0x12, 0xDD, 0xCC, 0xBB, 0xAA]);
+}
+
+#[test]
+fn strings_with_escapes() {
+    let image = assemble(".ascii \"a;b\\n\"\n.asciz \"\\x41\\\"\"").unwrap();
+
+    assert_eq!(image, b"a;b\nA\"\0".to_vec());
+}
+
+#[test]
+fn fill_and_align() {
+    let image = assemble(".byte 0x1\n.align 4\n.fill 2, 0xFF").unwrap();
+
+    assert_eq!(image, vec![0x01, 0, 0, 0, 0xFF, 0xFF]);
+}
+
+#[test]
+fn labels_on_data() {
+    let image = assemble("JMP :end\n:msg\n.asciz \"hi\"\n:end\n.word :msg").unwrap();
+
+    assert_eq!(image, vec![0x01, 0x08, 0, 0, 0, b'h', b'i', 0, 0x05, 0, 0, 0]);
+}
+
+#[test]
+fn value_too_large_for_byte() {
+    let diagnostics = assemble(".byte 0x100").unwrap_err();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "value 0x100 does not fit into the 1-byte data operand"
+    );
+}
+
+#[test]
+fn unknown_directive() {
+    let diagnostics = assemble(".bytes 0x1").unwrap_err();
+
+    assert_eq!(diagnostics[0].message, "unknown directive `.bytes`");
+}
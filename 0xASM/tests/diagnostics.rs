@@ -0,0 +1,28 @@
+use asm::{assemble, Severity};
+
+#[test]
+fn assembles_program() {
+    let image = assemble("MOVR 0x10, r1\nHALT").unwrap();
+
+    assert_eq!(image, vec![0x10, 0x10, 0, 0, 0, 0, 0, 0, 0, 0xFF]);
+}
+
+#[test]
+fn collects_all_errors() {
+    let diagnostics = assemble("MOVR 0xZZ, r1\nFOO\nADD 0x1, r9").unwrap_err();
+
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    assert_eq!(
+        diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+}
+
+#[test]
+fn caret_points_at_operand() {
+    let diagnostics = assemble("    ADD 0x1, r9").unwrap_err();
+
+    assert_eq!(diagnostics[0].span.start, 13);
+    assert!(diagnostics[0].to_string().ends_with("             ^^"));
+}
@@ -0,0 +1,94 @@
+use asm::{assemble_object, assemble_with, link, Options};
+use isa::{Devices, Executable, Segment};
+
+#[test]
+fn sections_become_segments() {
+    let options = Options {
+        base: Some(0x100),
+        entry: Some("main".to_string()),
+        ..Options::default()
+    };
+    let output = assemble_with(
+        ".data\n.byte 0x7\n.bss\n.space 8\n.text\nNOP\n:main\nHALT",
+        &options,
+    );
+    assert!(output.diagnostics.is_empty());
+
+    let devices = Devices {
+        screen: true,
+        hard_drive: false,
+    };
+    let executable = output.executable(devices);
+
+    assert_eq!(executable.entry, 0x101);
+    assert_eq!(executable.load_address, 0x100);
+    assert_eq!(executable.devices, devices);
+    assert_eq!(
+        executable.segments,
+        vec![
+            Segment {
+                address: 0x100,
+                data: vec![0x00, 0xFF],
+                bss_size: 0,
+            },
+            Segment {
+                address: 0x102,
+                data: vec![0x07],
+                bss_size: 0,
+            },
+            Segment {
+                address: 0x103,
+                data: Vec::new(),
+                bss_size: 8,
+            },
+        ]
+    );
+    assert_eq!(
+        Executable::from_bytes(&executable.to_bytes()),
+        Ok(executable)
+    );
+}
+
+#[test]
+fn entry_defaults_to_the_base() {
+    let options = Options {
+        base: Some(0x40),
+        ..Options::default()
+    };
+
+    assert_eq!(assemble_with("NOP", &options).entry, 0x40);
+}
+
+#[test]
+fn undefined_entry_point() {
+    let options = Options {
+        entry: Some("start".to_string()),
+        ..Options::default()
+    };
+    let output = assemble_with("NOP", &options);
+
+    assert_eq!(output.diagnostics.len(), 1);
+    assert_eq!(
+        output.diagnostics[0].message,
+        "entry point `start` is not defined"
+    );
+}
+
+#[test]
+fn linker_resolves_the_entry_point() {
+    let object = |source| {
+        assemble_object(source, &Options::default())
+            .into_result()
+            .unwrap()
+    };
+    let objects = vec![
+        ("a.o".to_string(), object("NOP\nNOP")),
+        ("b.o".to_string(), object(".global main\n:main\nHALT")),
+    ];
+    let options = Options {
+        entry: Some("main".to_string()),
+        ..Options::default()
+    };
+
+    assert_eq!(link(&objects, &options).output.entry, 2);
+}
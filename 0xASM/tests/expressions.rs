@@ -0,0 +1,87 @@
+use asm::assemble;
+
+fn word(source: &str) -> u32 {
+    let image = assemble(&format!(".word {}", source)).unwrap();
+
+    u32::from_le_bytes([image[0], image[1], image[2], image[3]])
+}
+
+#[test]
+fn operators_and_precedence() {
+    assert_eq!(word("1 + 2 * 3"), 7);
+    assert_eq!(word("(1 + 2) * 3"), 9);
+    assert_eq!(word("1 << 4 | 0b11 & ~1"), 0x12);
+    assert_eq!(word("17 % 5 ^ 0x10 >> 2"), 6);
+    assert_eq!(word("-1"), 0xFFFF_FFFF);
+}
+
+#[test]
+fn comparisons_and_logical_operators() {
+    assert_eq!(word("1 < 2 && 3 >= 3"), 1);
+    assert_eq!(word("1 << 2 == 4 || 0"), 1);
+    assert_eq!(word("2 <= 1 | 1 != 1"), 0);
+    assert_eq!(word("!0 + !5"), 1);
+    assert_eq!(word("-1 > 0"), 0);
+    assert_eq!(word("-1 < 0 && 0x7FFFFFFF > 0x80000000"), 1);
+}
+
+#[test]
+fn literals() {
+    assert_eq!(word("0o17 + 1_000 + 0xFF_FF"), 15 + 1000 + 0xFFFF);
+    assert_eq!(word("'a' | 0x20"), u32::from(b'a'));
+}
+
+#[test]
+fn constants_and_forward_labels() {
+    let source = "
+        .equ SCREEN, 0x1000
+        .define ROW SCREEN + 80
+        MOVR ROW + 2, r1
+        MOVR end - start, r2
+        :start
+        .asciz \"hi\"
+        :end
+        .word sizeof(start)
+    ";
+    let image = assemble(source).unwrap();
+
+    assert_eq!(&image[1..5], &0x1052u32.to_le_bytes());
+    assert_eq!(&image[10..14], &3u32.to_le_bytes());
+    assert_eq!(&image[image.len() - 4..], &3u32.to_le_bytes());
+}
+
+#[test]
+fn constant_as_status_bit() {
+    let image = assemble(".equ CARRY, 2\n:l\nBRBS CARRY, :l").unwrap();
+
+    assert_eq!(image[1], 2);
+}
+
+#[test]
+fn negative_values_fit_narrow_operands() {
+    assert_eq!(assemble(".byte -1, -128").unwrap(), vec![0xFF, 0x80]);
+    assert_eq!(
+        assemble(".byte -129").unwrap_err()[0].message,
+        "value 0xFFFFFF7F does not fit into the 1-byte data operand"
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        assemble(".word 1 / (2 - 2)").unwrap_err()[0].message,
+        "division by zero"
+    );
+    assert_eq!(
+        assemble(".equ A, B\n.equ B, A\n.word A").unwrap_err()[0].message,
+        "constant `A` is defined in terms of itself"
+    );
+    assert_eq!(
+        assemble(".word r1 + 1").unwrap_err()[0].message,
+        "registers can't be used in expressions, got `r1`"
+    );
+    assert_eq!(
+        assemble(".word (1 + 2").unwrap_err()[0].message,
+        "unexpected end of expression `(1 + 2`"
+    );
+}
@@ -0,0 +1,54 @@
+use asm::assemble;
+
+/// Asserts that both sources assemble to the same bytes.
+fn same(generic: &str, explicit: &str) {
+    assert_eq!(
+        assemble(generic).unwrap(),
+        assemble(explicit).unwrap(),
+        "{}",
+        generic
+    );
+}
+
+#[test]
+fn mov_picks_the_variant_from_the_operands() {
+    same("MOV 0x10, r1", "MOVR 0x10, r1");
+    same("MOV 0x10, [0x20]", "MOVM 0x10, 0x20");
+    same("MOV r1, r2", "MOVRR r1, r2");
+    same("MOV r1, [0x20]", "MOVRM r1, 0x20");
+    same("mov [0x20], r1", "MOVMR 0x20, r1");
+    same("MOV [r1], r2", "MOVRPR r1, r2");
+    same("MOV [r1 + 4], r2", "MOVROR r1, 4, r2");
+    same("MOV [r1 - 4], r2", "MOVROR r1, -4, r2");
+}
+
+#[test]
+fn arithmetic_accepts_either_operand_order() {
+    same("ADD r1, 0x5", "ADD 0x5, r1");
+    same("ADD r1, r2", "ADDR r1, r2");
+    same("SUB 0x5, r1", "SUBWR 0x5, r1");
+    same("AND 0xF, r1", "AND r1, 0xF");
+    same("SHL r1, r2", "LSFR r1, r2");
+    same("ROR r1, 3", "WRSF r1, 3");
+    same("PUSH r1\nCALL r2", "PUSHR r1\nCALLR r2");
+}
+
+#[test]
+fn branches_pick_the_comparison() {
+    same(":l\nBREQ 0x1, :l", ":l\nBREQ 0x1, :l");
+    same(":l\nBREQ r1, :l", ":l\nBREQR r1, :l");
+    same(":l\nBRLTE r1, 0x1, :l", ":l\nBRLTERW r1, 0x1, :l");
+    same(":l\nBRNQ r1, r2, :l", ":l\nBRNQRR r1, r2, :l");
+}
+
+#[test]
+fn unsupported_operands_list_the_accepted_forms() {
+    let diagnostics = assemble("MOV [0x10], [r1]").unwrap_err();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "MOV can't take `[address], [register]`, only `value, register` or `value, [address]` \
+         or `register, register` or `register, [address]` or `[address], register` \
+         or `[register], register` or `[register + offset], register`"
+    );
+}
@@ -0,0 +1,123 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use asm::{assemble_with, Options};
+
+/// Creates a fresh directory with the given files and returns its path.
+fn directory(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+    let directory = env::temp_dir().join(format!("asm-include-{}", name));
+    let _ = fs::remove_dir_all(&directory);
+
+    for (file, contents) in files {
+        let path = directory.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    directory
+}
+
+fn assemble_file(directory: &Path, include_paths: Vec<PathBuf>) -> asm::Output {
+    let file_name = directory.join("main.asm");
+    let source = fs::read_to_string(&file_name).unwrap();
+
+    assemble_with(
+        &source,
+        &Options {
+            file_name: file_name.to_string_lossy().to_string(),
+            include_paths,
+            ..Options::default()
+        },
+    )
+}
+
+#[test]
+fn includes_next_to_file_and_in_include_paths() {
+    let directory = directory(
+        "paths",
+        &[
+            (
+                "main.asm",
+                b".include \"local.asm\"\n.include \"screen.asm\"\nCALL :print",
+            ),
+            ("local.asm", b".equ SCREEN, 0x1000"),
+            ("lib/screen.asm", b":print\nMOVR SCREEN, r1"),
+        ],
+    );
+
+    let output = assemble_file(&directory, vec![directory.join("lib")]);
+
+    assert_eq!(
+        output.into_result().unwrap()[..6],
+        [0x10, 0x00, 0x10, 0, 0, 0]
+    );
+}
+
+#[test]
+fn incbin_embeds_raw_bytes() {
+    let directory = directory(
+        "incbin",
+        &[
+            ("main.asm", b":font\n.incbin \"font.bin\"\n.word :font"),
+            ("font.bin", &[0xDE, 0xAD, 0x00]),
+        ],
+    );
+
+    let image = assemble_file(&directory, Vec::new()).into_result().unwrap();
+
+    assert_eq!(image, vec![0xDE, 0xAD, 0x00, 0, 0, 0, 0]);
+}
+
+#[test]
+fn diagnostics_name_the_included_file() {
+    let directory = directory(
+        "diagnostics",
+        &[
+            ("main.asm", b"\n.include \"bad.asm\""),
+            ("bad.asm", b"NOP\nFOO"),
+        ],
+    );
+
+    let diagnostics = assemble_file(&directory, Vec::new())
+        .into_result()
+        .unwrap_err();
+
+    assert!(diagnostics[0].file.ends_with("bad.asm"));
+    assert_eq!(diagnostics[0].line, 2);
+    assert!(diagnostics[0].notes[0].file.ends_with("main.asm"));
+    assert_eq!(diagnostics[0].notes[0].line, 2);
+}
+
+#[test]
+fn include_cycle_is_an_error() {
+    let directory = directory(
+        "cycle",
+        &[
+            ("main.asm", b".include \"a.asm\""),
+            ("a.asm", b".include \"b.asm\""),
+            ("b.asm", b".include \"a.asm\""),
+        ],
+    );
+
+    let diagnostics = assemble_file(&directory, Vec::new())
+        .into_result()
+        .unwrap_err();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.starts_with("include cycle: "));
+    assert!(diagnostics[0].message.ends_with("a.asm"));
+}
+
+#[test]
+fn missing_file() {
+    let directory = directory("missing", &[("main.asm", b".include \"nope.asm\"")]);
+
+    let diagnostics = assemble_file(&directory, Vec::new())
+        .into_result()
+        .unwrap_err();
+
+    assert!(diagnostics[0]
+        .message
+        .starts_with("can't find `nope.asm` next to"));
+}
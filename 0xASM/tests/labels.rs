@@ -0,0 +1,96 @@
+use asm::{assemble, assemble_with, Options, Severity};
+
+#[test]
+fn undefined_label_suggests_known_label() {
+    let diagnostics = assemble(":print_string\nJMP :print_strng\nJMP :print_string").unwrap_err();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        "undefined label `print_strng`, did you mean `print_string`?"
+    );
+    assert_eq!(diagnostics[0].line, 2);
+}
+
+#[test]
+fn duplicate_label_names_both_locations() {
+    let diagnostics = assemble(":loop\nJMP :loop\n:loop").unwrap_err();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, 3);
+    assert_eq!(diagnostics[0].notes[0].line, 1);
+}
+
+#[test]
+fn unused_label_is_a_warning() {
+    let output = assemble_with(":unused\nHALT", &Options::default());
+
+    assert!(!output.has_errors());
+    assert_eq!(output.diagnostics[0].severity, Severity::Warning);
+    assert_eq!(
+        output.diagnostics[0].message,
+        "label `unused` is never used"
+    );
+}
+
+#[test]
+fn local_labels_belong_to_the_last_global_label() {
+    let source = ":first\n:.loop\nJMP :.loop\n:second\n:.loop\nJMP .loop\nJMP first.loop";
+    let image = assemble(source).unwrap();
+
+    assert_eq!(&image[1..5], &0u32.to_le_bytes());
+    assert_eq!(&image[6..10], &5u32.to_le_bytes());
+    assert_eq!(&image[11..15], &0u32.to_le_bytes());
+}
+
+#[test]
+fn local_labels_are_reported_with_their_scope() {
+    let diagnostics = assemble(":print\n:.loop\nJMP :.lop").unwrap_err();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "undefined label `print.lop`, did you mean `print.loop`?"
+    );
+}
+
+#[test]
+fn anonymous_labels_reference_the_next_or_previous_definition() {
+    let source = ":1\nJMP 1f\n:1\nJMP 1b\nJMP :1b\nJMP 1f\n:1";
+    let image = assemble(source).unwrap();
+
+    assert_eq!(&image[1..5], &5u32.to_le_bytes());
+    assert_eq!(&image[6..10], &5u32.to_le_bytes());
+    assert_eq!(&image[11..15], &5u32.to_le_bytes());
+    assert_eq!(&image[16..20], &20u32.to_le_bytes());
+}
+
+#[test]
+fn missing_anonymous_labels() {
+    let diagnostics = assemble("JMP 1b\n:1\nJMP 2f").unwrap_err();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "no anonymous label `1` before this line"
+    );
+    assert_eq!(
+        diagnostics[1].message,
+        "no anonymous label `2` after this line"
+    );
+}
+
+#[test]
+fn label_names_are_checked() {
+    let diagnostics = assemble(":foo MOVR 1, r1\nJMP :foo").unwrap_err();
+
+    assert_eq!(diagnostics[0].message, "expected a label name");
+    assert_eq!(diagnostics[0].line, 1);
+    assert_eq!(
+        (diagnostics[0].span.start, diagnostics[0].span.end),
+        (1, 15)
+    );
+
+    assert_eq!(
+        assemble(":\nHALT").unwrap_err()[0].message,
+        "expected a label name"
+    );
+}
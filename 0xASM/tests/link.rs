@@ -0,0 +1,179 @@
+use asm::{assemble, assemble_object, link, Object, Options, Word};
+
+/// Assembles every source into an object named after it.
+fn objects(sources: &[(&str, &str)]) -> Vec<(String, Object)> {
+    sources
+        .iter()
+        .map(|(name, source)| {
+            let options = Options {
+                file_name: name.to_string(),
+                ..Options::default()
+            };
+            let object = assemble_object(source, &options).into_result().unwrap();
+            (name.replace(".asm", ".o"), object)
+        })
+        .collect()
+}
+
+#[test]
+fn links_external_symbols() {
+    let objects = objects(&[
+        ("main.asm", ".extern print\nCALL :print\nHALT"),
+        ("print.asm", ".global print\n:print\nRET"),
+    ]);
+    let linked = link(&objects, &Options::default());
+
+    assert!(linked.output.diagnostics.is_empty());
+    assert_eq!(linked.output.image, vec![0x02, 0x06, 0, 0, 0, 0xFF, 0x04]);
+}
+
+#[test]
+fn merges_sections_and_relocates_local_labels() {
+    let objects = objects(&[
+        ("a.asm", "NOP\n.data\n.byte 0x1"),
+        (
+            "b.asm",
+            ".data\n.align 2\n:value\n.half 0x7\n.text\nMOVMR :value + 1, r1",
+        ),
+    ]);
+    let options = Options {
+        base: Some(0x100),
+        ..Options::default()
+    };
+    let output = link(&objects, &options).output;
+
+    // .text of both objects, then .data with b's part aligned to 2
+    assert_eq!(output.base, 0x100);
+    assert_eq!(output.image.len(), 10 + 4);
+    assert_eq!(&output.image[1..6], &[0x14, 0x0D, 0x01, 0, 0]);
+    assert_eq!(&output.image[10..], &[0x01, 0, 0x07, 0]);
+}
+
+#[test]
+fn reports_duplicate_and_undefined_symbols() {
+    let objects = objects(&[
+        (
+            "a.asm",
+            ".global start\n:start\n.extern missing\nJMP :missing",
+        ),
+        ("b.asm", ".global start\n:start\nHALT"),
+    ]);
+    let diagnostics = link(&objects, &Options::default()).output.diagnostics;
+
+    assert_eq!(
+        diagnostics[0].message,
+        "symbol `start` is defined twice, at a.asm:2 in a.o and at b.asm:2 in b.o"
+    );
+    assert_eq!(diagnostics[0].file, "b.o");
+    assert_eq!(
+        diagnostics[1].message,
+        "undefined symbol `missing`, referenced at a.asm:4"
+    );
+}
+
+#[test]
+fn writes_a_link_map() {
+    let objects = objects(&[
+        ("main.asm", ".extern print\n:main\nCALL :print"),
+        (
+            "print.asm",
+            ".global print\n:print\nRET\n.bss\n:buffer\n.space 16",
+        ),
+    ]);
+    let map = link(&objects, &Options::default()).map;
+
+    assert_eq!(
+        map.to_string(),
+        "Sections\n\
+        \x20 0x00000000 0x00000006 .text\n\
+        \x20   0x00000000 0x00000005 main.o\n\
+        \x20   0x00000005 0x00000001 print.o\n\
+        \x20 0x00000006 0x00000010 .bss (bss)\n\
+        \x20   0x00000006 0x00000010 print.o\n\
+        \n\
+        Symbols\n\
+        \x20 0x00000000 main                     main.o (local)\n\
+        \x20 0x00000005 print                    print.o\n\
+        \x20 0x00000006 buffer                   print.o (local)\n"
+    );
+}
+
+#[test]
+fn objects_round_trip_through_bytes() {
+    let objects = objects(&[(
+        "main.asm",
+        ".extern print\n.global SIZE\n.equ SIZE, 4\n:main\nCALL :print\n.data\n.word :main",
+    )]);
+    let object = &objects[0].1;
+
+    assert_eq!(object.relocations.len(), 2);
+    assert_eq!(Object::from_bytes(&object.to_bytes()).as_ref(), Ok(object));
+    assert_eq!(
+        Object::from_bytes(b"ELF\x01"),
+        Err("not an object file".to_string())
+    );
+}
+
+#[test]
+fn external_symbols_need_the_linker() {
+    let diagnostics = assemble(".extern print\nCALL :print").unwrap_err();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "`print` is an external symbol, assemble with -c and link the object files"
+    );
+}
+
+#[test]
+fn sections_past_the_end_of_the_address_space() {
+    let objects = objects(&[("main.asm", ":start\nMOVR 1, r1\nJMP :start")]);
+    let options = Options {
+        base: Some(0xFFFF_FFF8),
+        ..Options::default()
+    };
+    let linked = link(&objects, &options);
+
+    assert_eq!(
+        linked.output.diagnostics[0].message,
+        "section `.text` of 14 byte(s) at 0xFFFFFFF8 does not fit in the address space"
+    );
+}
+
+#[test]
+fn merged_sections_past_the_end_of_the_address_space() {
+    let mut objects = objects(&[
+        ("a.asm", ".bss\n.space 0x10"),
+        ("b.asm", ".bss\n.space 0x10"),
+        ("c.asm", ".bss\n.space 0x10"),
+    ]);
+    for (_, object) in &mut objects {
+        object.sections[0].size = 0x6000_0000;
+    }
+    let diagnostics = link(&objects, &Options::default()).output.diagnostics;
+
+    assert_eq!(diagnostics[0].file, "c.o");
+    assert_eq!(
+        diagnostics[0].message,
+        "section `.bss` of 1610612736 byte(s) does not fit in the address space"
+    );
+
+    objects[0].1.sections[0].alignment = 0x8000_0000;
+    objects[1].1.sections[0].alignment = 3;
+    let diagnostics = link(&objects[..2], &Options::default()).output.diagnostics;
+    assert_eq!(diagnostics[0].file, "b.o");
+    assert_eq!(
+        diagnostics[0].message,
+        "section `.bss` can't be aligned to 0x3 and to the alignments before"
+    );
+}
+
+#[test]
+fn relocations_past_the_end_of_the_address_space() {
+    let mut objects = objects(&[("main.asm", ":start\nJMP :start")]);
+    objects[0].1.relocations[0].offset = Word::MAX;
+    let diagnostics = link(&objects, &Options::default()).output.diagnostics;
+
+    assert!(diagnostics[0]
+        .message
+        .ends_with("is outside of section `.text`"));
+}
@@ -0,0 +1,75 @@
+use asm::{assemble_with, DefinedSymbol, Options};
+
+#[test]
+fn lines_have_addresses_and_bytes() {
+    let options = Options {
+        base: Some(0x100),
+        ..Options::default()
+    };
+    let output = assemble_with(":start\n    NOP\n.data\n    .half 0x1234\n", &options);
+    let lines: Vec<(usize, u32, Vec<u8>)> = output
+        .listing
+        .iter()
+        .map(|l| (l.line, l.address, l.bytes.clone()))
+        .collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            (1, 0x100, vec![]),
+            (2, 0x100, vec![0x00]),
+            (3, 0x101, vec![]),
+            (4, 0x101, vec![0x34, 0x12]),
+        ]
+    );
+}
+
+#[test]
+fn macro_invocations_are_listed_with_their_expansion() {
+    let source = ".macro TWICE\n    NOP\n    NOP\n.endm\n    TWICE";
+    let output = assemble_with(source, &Options::default());
+    let listing = output.listing();
+
+    assert!(listing.contains("    5  00000000                               TWICE\n"));
+    assert!(listing.contains("    2+ 00000000  00                           NOP\n"));
+    assert!(listing.contains("    3+ 00000001  00                           NOP\n"));
+}
+
+#[test]
+fn symbols_are_sorted_by_value() {
+    let source = ".equ SIZE, 0x20\n:start\nMOVR SIZE, r1\n:end\nJMP :start\nJMP :end";
+    let output = assemble_with(source, &Options::default());
+
+    let symbol = |name: &str, value, constant| DefinedSymbol {
+        name: name.to_string(),
+        value,
+        constant,
+    };
+    assert_eq!(
+        output.symbols,
+        vec![
+            symbol("start", 0, false),
+            symbol("end", 9, false),
+            symbol("SIZE", 0x20, true),
+        ]
+    );
+    assert!(output
+        .listing()
+        .ends_with("Symbols\n  00000000  start\n  00000009  end\n  00000020  SIZE (constant)\n"));
+}
+
+#[test]
+fn debug_info_describes_addresses() {
+    let options = Options {
+        file_name: "lib.asm".to_string(),
+        base: Some(0x100),
+        ..Options::default()
+    };
+    let output = assemble_with(".equ SIZE, 4\n:print\n    NOP\n    JMP :print", &options);
+    assert!(!output.has_errors());
+    let info = output.debug_info();
+
+    assert_eq!(info.symbols.len(), 1);
+    assert_eq!(info.lines.len(), 2);
+    assert_eq!(info.describe(0x103), "print+0x03 (lib.asm:4)");
+}
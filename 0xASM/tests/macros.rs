@@ -0,0 +1,94 @@
+use asm::{assemble, assemble_with, Options};
+
+#[test]
+fn parameters_and_defaults() {
+    let source = "
+        .macro PUTC char, offset=0
+            MOVR \\char, r1
+            MOVRM r1, 0x1000 + \\offset
+        .endm
+        PUTC 'a', 4
+        putc 'b'
+        PUTC offset=2, char='c'
+    ";
+    let expected = assemble(
+        "MOVR 'a', r1\nMOVRM r1, 0x1004\nMOVR 'b', r1\nMOVRM r1, 0x1000\nMOVR 'c', r1\nMOVRM r1, 0x1002",
+    );
+
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn local_labels_are_unique_per_expansion() {
+    let source = "
+        .macro WAIT
+        :loop
+            DEC r1
+            BRNQ 0x0, :loop
+        .endm
+        :loop
+        WAIT
+        WAIT
+        JMP :loop
+    ";
+    let image = assemble(source).unwrap();
+
+    // the second expansion jumps back to its own loop
+    assert_eq!(&image[24..28], &14u32.to_le_bytes());
+    assert_eq!(&image[29..33], &0u32.to_le_bytes());
+}
+
+#[test]
+fn nested_invocations() {
+    let source = "
+        .macro SAVE reg
+            PUSHR \\reg
+        .endm
+        .macro SAVE2 a, b
+            SAVE \\a
+            SAVE \\b
+        .endm
+        SAVE2 r1, acc
+    ";
+
+    assert_eq!(assemble(source), assemble("PUSHR r1\nPUSHR acc"));
+}
+
+#[test]
+fn errors_point_at_definition_and_call_site() {
+    let source = ".macro SETR value\n    MOVR \\value, r1\n.endm\nSETR r2";
+    let output = assemble_with(source, &Options::default());
+
+    let error = &output.diagnostics[0];
+    assert_eq!(error.line, 2);
+    assert_eq!(error.source, "    MOVR r2, r1");
+    assert_eq!(error.notes[0].line, 4);
+    assert_eq!(error.notes[0].message, "in expansion of macro `SETR`");
+}
+
+#[test]
+fn argument_errors() {
+    let source = ".macro M a, b\n.endm\nM 1\nM 1, 2, 3\nM c=1\n.macro N\n    \\x\n.endm";
+    let messages: Vec<String> = assemble(source)
+        .unwrap_err()
+        .into_iter()
+        .map(|d| d.message)
+        .collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "missing argument `b` of macro `M`",
+            "macro `M` expects at most 2 argument(s)",
+            "macro `M` has no parameter `c`",
+            "macro `N` has no parameter `x`",
+        ]
+    );
+}
+
+#[test]
+fn recursive_macro() {
+    let diagnostics = assemble(".macro LOOP\n    LOOP\n.endm\nLOOP").unwrap_err();
+
+    assert!(diagnostics[0].message.starts_with("macro `LOOP` is nested more than"));
+}
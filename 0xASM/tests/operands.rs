@@ -0,0 +1,57 @@
+use asm::assemble;
+
+fn error(source: &str) -> String {
+    let diagnostics = assemble(source).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+
+    diagnostics[0].message.clone()
+}
+
+#[test]
+fn swapped_operands() {
+    let diagnostics = assemble("MOVR r1, 0x10").unwrap_err();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "operand 1 of MOVR must be an immediate, got register r1"
+    );
+    assert_eq!(
+        diagnostics[1].message,
+        "operand 2 of MOVR must be a register, got immediate 0x10"
+    );
+}
+
+#[test]
+fn immediate_where_register_expected() {
+    assert_eq!(
+        error("MOVR 0x10, 0x20"),
+        "operand 2 of MOVR must be a register, got immediate 0x20"
+    );
+}
+
+#[test]
+fn status_bit_is_checked() {
+    assert_eq!(
+        error(":l\nBRBS :l, :l"),
+        "operand 1 of BRBS must be a status bit, got label :l"
+    );
+    assert!(error(":l\nBRBS 32, :l").starts_with("status bit `32` of BRBS is out of range"));
+}
+
+#[test]
+fn labels_are_valid_addresses_and_immediates() {
+    assert!(assemble(":msg\nMOVR :msg, r1\nMOVMR :msg, r2\nJMP :msg").is_ok());
+}
+
+#[test]
+fn special_registers() {
+    let image = assemble("MOVRR acc, r1\nMOVROR sp, 0x4, r2").unwrap();
+
+    assert_eq!(&image[1..9], &[36, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&image[10..14], &[44, 0, 0, 0]);
+}
+
+#[test]
+fn unknown_register() {
+    assert_eq!(error("INC r9"), "invalid register `r9`");
+}
@@ -0,0 +1,91 @@
+use asm::{assemble, assemble_with, Options};
+
+#[test]
+fn labels_are_offset_by_the_base() {
+    let options = Options {
+        base: Some(0x104),
+        ..Options::default()
+    };
+    let output = assemble_with("JMP :end\n:end\nHALT", &options);
+
+    assert_eq!(output.base, 0x104);
+    assert_eq!(output.image, vec![0x01, 0x09, 0x01, 0, 0, 0xFF]);
+}
+
+#[test]
+fn first_org_sets_the_base() {
+    let output = assemble_with(".org 0x200\n:start\nJMP :start", &Options::default());
+
+    assert_eq!(output.base, 0x200);
+    assert_eq!(output.image, vec![0x01, 0x00, 0x02, 0, 0]);
+}
+
+#[test]
+fn later_org_pads_with_zeros() {
+    let image = assemble(".org 0x10\n.byte 0x1\n.org 0x14\n:data\n.word :data").unwrap();
+
+    assert_eq!(image, vec![0x01, 0, 0, 0, 0x14, 0, 0, 0]);
+}
+
+#[test]
+fn org_relative_to_given_base() {
+    let options = Options {
+        base: Some(0x100),
+        ..Options::default()
+    };
+
+    assert_eq!(
+        assemble_with(".org 0x102\nNOP", &options).image,
+        vec![0, 0, 0]
+    );
+    assert_eq!(
+        assemble_with(".org 0x10", &options).diagnostics[0].message,
+        ".org 0x10 is before the current address 0x100"
+    );
+}
+
+#[test]
+fn sections_past_the_end_of_the_address_space() {
+    let options = Options {
+        base: Some(0xFFFF_FFF8),
+        ..Options::default()
+    };
+    let output = assemble_with(":start\nMOVR 1, r1\nJMP :start\n:end\n.byte 1", &options);
+
+    assert!(output.has_errors());
+    assert_eq!(
+        output.diagnostics[0].message,
+        "section `.text` of 15 byte(s) at 0xFFFFFFF8 does not fit in the address space"
+    );
+    assert_eq!(
+        output.executable(Default::default()).segments[0].data.len(),
+        7
+    );
+}
+
+#[test]
+fn sections_growing_past_the_end_of_the_address_space() {
+    let source = ".org 0xFFFFFF00\n.fill 0x60\n.fill 0x60\n.fill 0x60";
+    let diagnostics = assemble(source).unwrap_err();
+    assert_eq!(
+        diagnostics[0].message,
+        "section `.text` does not fit in the address space"
+    );
+    assert_eq!(diagnostics[0].line, 4);
+
+    let source = ".bss\n.org 0xFFFFFF00\n.space 0x60\n.space 0x60\n.space 0x60";
+    assert_eq!(assemble(source).unwrap_err()[0].line, 5);
+
+    let source = ".org 0xFFFFFFF0\nNOP\n.align 0x80000000";
+    assert_eq!(
+        assemble(source).unwrap_err()[0].message,
+        "section `.text` does not fit in the address space"
+    );
+
+    let diagnostics = assemble(".align 0x80000000\n.align 3").unwrap_err();
+    assert_eq!(
+        diagnostics[0].message,
+        "`.text` can't be aligned to 0x3 and to the alignments before"
+    );
+    assert_eq!(diagnostics[0].line, 2);
+}
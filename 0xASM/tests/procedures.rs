@@ -0,0 +1,160 @@
+use asm::{assemble, assemble_with, Options};
+
+#[test]
+fn call_pushes_the_arguments_and_their_count() {
+    let source = "
+        .proc draw(x, y, color)
+            RET
+        .endproc
+        CALL :draw, 1, r2, 'a'
+        CALL helper
+        :helper
+    ";
+    let expected =
+        assemble(":draw\nRET\nPUSH 1\nPUSH r2\nPUSH 'a'\nPUSH 3\nCALL :draw\nCALL helper\n:helper");
+
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn procedures_defined_later_get_the_count_too() {
+    let source = "CALL later\nHALT\n.proc later()\nRET\n.endproc";
+
+    assert_eq!(
+        assemble(source),
+        assemble("PUSH 0\nCALL later\nHALT\n:later\nRET")
+    );
+}
+
+#[test]
+fn arguments_and_locals_are_relative_to_fp() {
+    let source = "
+        .proc sum(a, b)
+        .local total, i
+            MOV [fp + a], r1
+            MOV [fp + b], r2
+            MOV [fp + i], r3
+        .endproc
+    ";
+    let expected =
+        assemble("PUSH 0\nPUSH 0\nMOV [fp + 52], r1\nMOV [fp + 48], r2\nMOV [fp - 4], r3");
+
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn expansions_are_listed() {
+    let output = assemble_with(".proc f(a)\nRET\n.endproc\nCALL f, 7", &Options::default());
+    let lines: Vec<(usize, bool, &str)> = output
+        .listing
+        .iter()
+        .map(|line| (line.line, line.expanded, line.text.as_str()))
+        .collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            (1, false, ".proc f(a)"),
+            (1, true, ":f"),
+            (1, true, ".equ f.a, 48"),
+            (2, false, "RET"),
+            (3, false, ".endproc"),
+            (4, false, "CALL f, 7"),
+            (4, true, "PUSH 7"),
+            (4, true, "PUSH 1"),
+            (4, true, "CALL f"),
+        ]
+    );
+}
+
+#[test]
+fn procedure_bodies_are_listed_as_written() {
+    let source = ".proc f(a)\n.local i\nMOV [fp + i], r1\nCALL f, a\nRET\n.endproc";
+    let output = assemble_with(source, &Options::default());
+    let lines: Vec<(usize, &str)> = output
+        .listing
+        .iter()
+        .map(|line| (line.line, line.text.as_str()))
+        .collect();
+
+    assert!(!output.has_errors());
+    assert_eq!(
+        &lines[6..],
+        &[
+            (3, "MOV [fp + i], r1"),
+            (4, "CALL f, a"),
+            (4, "MOV [fp + a], acc"),
+            (4, "PUSH acc"),
+            (4, "PUSH 1"),
+            (4, "CALL f"),
+            (5, "RET"),
+            (6, ".endproc"),
+        ]
+    );
+}
+
+#[test]
+fn memory_arguments_overwrite_acc() {
+    let source = "MOV 7, acc\nCALL f, [0x100], acc\nHALT\n.proc f(a, b)\nRET\n.endproc";
+    let expected =
+        assemble("MOV 7, acc\nMOV [0x100], acc\nPUSH acc\nPUSH acc\nPUSH 2\nCALL f\nHALT\n:f\nRET");
+
+    // b is the value loaded for a, not 7
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn errors() {
+    let diagnostics = assemble(".proc f(a, b)\nRET\n.endproc\nCALL f, 1").unwrap_err();
+    assert_eq!(
+        diagnostics[0].message,
+        "procedure `f` takes 2 argument(s), got 1"
+    );
+    assert_eq!(diagnostics[0].notes[0].message, "defined here");
+
+    assert_eq!(
+        assemble(".proc f()\nNOP\n.local i\n.endproc").unwrap_err()[0].message,
+        "locals are pushed first, `.local` has to come before the instructions of the procedure"
+    );
+    assert_eq!(
+        assemble(".proc f(a, a)\n.endproc").unwrap_err()[0].message,
+        "argument `a` is given twice"
+    );
+    assert_eq!(
+        assemble(".proc f\nRET").unwrap_err()[0].message,
+        "`.proc f` without matching `.endproc`"
+    );
+    assert_eq!(
+        assemble(".local i").unwrap_err()[0].message,
+        "`.local` outside of `.proc`"
+    );
+}
+
+#[test]
+fn calls_pass_the_values_of_arguments_and_locals() {
+    let source = "
+        .proc f(a)
+        .local i
+            CALL g, a, i, [0x100], 5
+            RET
+        .endproc
+        .proc g(w, x, y, z)
+            RET
+        .endproc
+    ";
+    let expected = assemble(
+        ":f\nPUSH 0\nMOV [fp + 48], acc\nPUSH acc\nMOV [fp + 0], acc\nPUSH acc\nMOV [0x100], acc\nPUSH acc\nPUSH 5\nPUSH 4\nCALL g\nRET\n:g\nRET",
+    );
+
+    assert_eq!(assemble(source), expected);
+
+    let diagnostics = assemble(".proc f(a)\nCALL f, a + 4\nRET\n.endproc").unwrap_err();
+    assert_eq!(
+        diagnostics[0].message,
+        "arguments and locals of `f` can only be passed to `CALL` on their own"
+    );
+    assert_eq!(
+        (diagnostics[0].span.start, diagnostics[0].span.end),
+        (8, 13)
+    );
+}
@@ -0,0 +1,109 @@
+use asm::{assemble, assemble_with, Options};
+
+#[test]
+fn pseudo_instructions_expand_to_instructions() {
+    let source = "
+        CLR r1
+        NEG r2
+        BRZ :end
+        BRNZ :end
+        BRC :end
+        BRNC :end
+        :end
+    ";
+    let expected = assemble(
+        "MOVR 0, r1\nSUBWR 0, r2\nMOVRR acc, r2\nBRBS 0, :end\nBRBC 0, :end\nBRBS 1, :end\nBRBC 1, :end\n:end",
+    );
+
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn lea_loads_addresses() {
+    let source = "
+        LEA :table, r1
+        LEA [:table], r2
+        LEA [fp], r3
+        LEA [fp + 8], r4
+        LEA [sp - 4], r5
+        :table
+    ";
+    let expected = assemble(
+        "MOVR :table, r1\nMOVR :table, r2\nMOVRR fp, r3\nADD fp, 8\nMOVRR acc, r4\nADD sp, -(4)\nMOVRR acc, r5\n:table",
+    );
+
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn pusha_and_popa_save_the_registers() {
+    let saved = ["acc", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8"];
+    let pushes: Vec<String> = saved.iter().map(|r| format!("PUSHR {}", r)).collect();
+    let pops: Vec<String> = saved.iter().rev().map(|r| format!("POP {}", r)).collect();
+
+    assert_eq!(
+        assemble("PUSHA\nPOPA"),
+        assemble(&format!("{}\n{}", pushes.join("\n"), pops.join("\n")))
+    );
+}
+
+#[test]
+fn branches_compare_the_operands_of_cmp() {
+    let source = "
+        CMP r1, 10
+        BRLT :less
+        BREQ :equal
+        CMP r1, r2
+        BRGTE :less
+        BRNQ 5, :less
+        :less
+        :equal
+    ";
+    let expected = assemble(
+        "MOVRR r1, acc\nBRLT 10, :less\nBREQ 10, :equal\nMOVRR r1, acc\nBRGTER r2, :less\nBRNQ 5, :less\n:less\n:equal",
+    );
+
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn expansions_are_listed() {
+    let output = assemble_with("CLR r1\nCMP r1, 2\nBREQ :x\n:x", &Options::default());
+    let lines: Vec<(usize, bool, &str)> = output
+        .listing
+        .iter()
+        .map(|line| (line.line, line.expanded, line.text.as_str()))
+        .collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            (1, false, "CLR r1"),
+            (1, true, "MOVR 0, r1"),
+            (2, false, "CMP r1, 2"),
+            (2, true, "MOV r1, acc"),
+            (3, false, "BREQ :x"),
+            (3, true, "BREQ 2, :x"),
+            (4, false, ":x"),
+        ]
+    );
+}
+
+#[test]
+fn errors() {
+    let diagnostics = assemble("CMP r1, 2\n:x\nBREQ :x").unwrap_err();
+    assert_eq!(
+        diagnostics[0].message,
+        "BREQ with one operand compares the operands of CMP, which has to come right before it"
+    );
+    assert_eq!(diagnostics[0].span.start, 0);
+
+    assert_eq!(
+        assemble("CLR r1, r2").unwrap_err()[0].message,
+        "CLR expects 1 operand(s), got 2"
+    );
+    assert_eq!(
+        assemble("PUSHA r1").unwrap_err()[0].message,
+        "PUSHA expects 0 operand(s), got 1"
+    );
+}
@@ -0,0 +1,94 @@
+use asm::{assemble, assemble_with, Layout, Options, Section};
+
+fn layout(source: &str) -> Options {
+    Options {
+        layout: Some(Layout {
+            file_name: "layout.txt".to_string(),
+            source: source.to_string(),
+        }),
+        ..Options::default()
+    }
+}
+
+#[test]
+fn code_comes_before_data_by_default() {
+    let image = assemble(".data\n:msg\n.byte 0x1\n.text\nJMP :msg").unwrap();
+
+    assert_eq!(image, vec![0x01, 0x05, 0, 0, 0, 0x01]);
+}
+
+#[test]
+fn bss_only_reserves_space() {
+    let source = ".bss\n:buffer\n.space 512\n.text\nMOVR :buffer, r1\nHALT";
+    let output = assemble_with(source, &Options::default());
+
+    assert_eq!(output.image.len(), 10);
+    assert_eq!(&output.image[1..5], &10u32.to_le_bytes());
+    assert_eq!(
+        output.sections[1],
+        Section {
+            name: ".bss".to_string(),
+            address: 10,
+            size: 512,
+            bss: true,
+        }
+    );
+}
+
+#[test]
+fn bss_rejects_data_and_code() {
+    let diagnostics = assemble(".bss\n.byte 0x1\nNOP").unwrap_err();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "`.bss` only reserves space, use .space, .fill or .align"
+    );
+    assert_eq!(
+        diagnostics[1].message,
+        "`.bss` only reserves space, instructions belong into `.text`"
+    );
+}
+
+#[test]
+fn sections_are_aligned() {
+    let image = assemble("NOP\n.data\n.align 4\n.word 0x1").unwrap();
+
+    assert_eq!(image, vec![0, 0, 0, 0, 0x01, 0, 0, 0]);
+}
+
+#[test]
+fn layout_file_orders_and_places_sections() {
+    let source = ".section .rodata\n.byte 0x2\n.bss\n.space 2\n.data\n.byte 0x3\n.text\nNOP";
+    let output = assemble_with(
+        source,
+        &layout("; order\n.rodata 0x100\n.bss\n.text\n.data 0x108"),
+    );
+
+    assert_eq!(output.base, 0x100);
+    // bss between other sections is filled with zeros
+    assert_eq!(output.image, vec![0x02, 0, 0, 0x00, 0, 0, 0, 0, 0x03]);
+}
+
+#[test]
+fn sections_missing_from_the_layout() {
+    let output = assemble_with(".data\n.byte 0x1\nNOP", &layout(".text"));
+
+    assert_eq!(
+        output.diagnostics[0].message,
+        "section `.data` is missing from the layout file layout.txt"
+    );
+}
+
+#[test]
+fn overlapping_sections() {
+    let output = assemble_with(
+        ".data\n.word 0x1\n.text\nNOP",
+        &layout(".text 0x10\n.data 0x10"),
+    );
+
+    assert_eq!(
+        output.diagnostics[0].message,
+        "section `.data` at 0x10 is before the current address 0x11"
+    );
+    assert_eq!(output.diagnostics[0].file, "layout.txt");
+}
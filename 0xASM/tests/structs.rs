@@ -0,0 +1,78 @@
+use asm::{assemble, assemble_with, Options};
+
+#[test]
+fn fields_are_offsets_and_size_is_the_total() {
+    let source = "
+        .struct Sprite
+        .field x
+        .field y, 4
+        .field tile, 1
+        .field flags, 2 + 1
+        .endstruct
+        MOVROR r1, Sprite.y, r2
+        .byte Sprite.tile, Sprite.flags, Sprite.size
+        .fill Sprite.size * 2, 0
+    ";
+    let expected = assemble("MOVROR r1, 4, r2\n.byte 8, 9, 12\n.fill 24, 0");
+
+    assert_eq!(assemble(source), expected);
+}
+
+#[test]
+fn structs_can_contain_structs_and_use_constants() {
+    let source = "
+        .equ NAME_LENGTH, 6
+        .struct Point
+        .field x
+        .field y
+        .endstruct
+        .struct Player
+        .field position, Point.size
+        .field name, NAME_LENGTH
+        .field score, 2
+        .endstruct
+        .byte Player.position + Point.y, Player.score, Player.size
+    ";
+
+    assert_eq!(assemble(source), Ok(vec![4, 14, 16]));
+}
+
+#[test]
+fn fields_are_listed_as_constants() {
+    let output = assemble_with(
+        ".struct Pair\n.field a, 1\n.field b, 1\n.endstruct",
+        &Options::default(),
+    );
+    let names: Vec<(&str, u32)> = output
+        .symbols
+        .iter()
+        .map(|symbol| (symbol.name.as_str(), symbol.value))
+        .collect();
+
+    assert_eq!(names, vec![("Pair.a", 0), ("Pair.b", 1), ("Pair.size", 2)]);
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        assemble(".struct Point\n.field x\n").unwrap_err()[0].message,
+        "`.struct Point` without matching `.endstruct`"
+    );
+    assert_eq!(
+        assemble(".struct Point\nNOP\n.endstruct").unwrap_err()[0].message,
+        "only `.field` can be used inside of `.struct Point`"
+    );
+    assert_eq!(
+        assemble(".field x, 4").unwrap_err()[0].message,
+        "`.field` without `.struct`"
+    );
+    assert_eq!(
+        assemble(".struct Point\n.field x\n.field x\n.endstruct").unwrap_err()[0].message,
+        "constant `Point.x` is already defined at <source>:2"
+    );
+    assert_eq!(
+        assemble(".struct Point\n.field x, LATER\n.endstruct\n.equ LATER, 4").unwrap_err()[0]
+            .message,
+        "undefined symbol `LATER`"
+    );
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use crate::Word;
+
+/// First line of a debug info file, followed by the format version.
+pub const DEBUG_INFO_MAGIC: &str = "0xDBG";
+pub const DEBUG_INFO_VERSION: u32 = 1;
+
+/// The bytes of one source line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineInfo {
+    pub address: Word,
+    pub size: Word,
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+}
+
+/// A label and its address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolInfo {
+    pub name: String,
+    pub address: Word,
+}
+
+/// Maps addresses back to source lines and labels, written by the assembler next to the
+/// program and read by the VM.
+///
+/// The file is text, one record per line:
+/// ```text
+/// 0xDBG 1
+/// symbol 0x00000104 print_string
+/// line 0x00000104 9 lib.asm:42
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugInfo {
+    pub lines: Vec<LineInfo>,
+    pub symbols: Vec<SymbolInfo>,
+}
+
+impl DebugInfo {
+    /// Returns the closest label at or before `address`.
+    pub fn symbol(&self, address: Word) -> Option<&SymbolInfo> {
+        // max_by_key picks the last of equal addresses, so the first defined label wins
+        self.symbols
+            .iter()
+            .rev()
+            .filter(|s| s.address <= address)
+            .max_by_key(|s| s.address)
+    }
+
+    /// Returns the first label defined exactly at `address`.
+    pub fn label(&self, address: Word) -> Option<&str> {
+        self.symbols
+            .iter()
+            .find(|s| s.address == address)
+            .map(|s| s.name.as_str())
+    }
+
+    /// Returns the source line whose bytes contain `address`.
+    pub fn line(&self, address: Word) -> Option<&LineInfo> {
+        self.lines
+            .iter()
+            .find(|l| l.address <= address && address - l.address < l.size)
+    }
+
+    /// Describes an address like `print_string+0x0C (lib.asm:42)`, falling back to the plain
+    /// address for the parts that are unknown.
+    pub fn describe(&self, address: Word) -> String {
+        let mut description = match self.symbol(address) {
+            Some(s) if s.address == address => s.name.clone(),
+            Some(s) => format!("{}+0x{:02X}", s.name, address - s.address),
+            None => format!("0x{:08X}", address),
+        };
+        if let Some(l) = self.line(address) {
+            description.push_str(&format!(" ({}:{})", l.file, l.line));
+        }
+
+        description
+    }
+
+    /// Parses the text written by `Display`.
+    pub fn parse(text: &str) -> Result<DebugInfo, String> {
+        let mut records = text.lines().enumerate();
+        let header = records.next().map(|(_, l)| l.trim()).unwrap_or("");
+        if header != format!("{} {}", DEBUG_INFO_MAGIC, DEBUG_INFO_VERSION) {
+            return Err(format!(
+                "not a debug info file, expected `{} {}` in the first line",
+                DEBUG_INFO_MAGIC, DEBUG_INFO_VERSION
+            ));
+        }
+
+        let mut info = DebugInfo::default();
+        for (n, record) in records {
+            let invalid = || format!("invalid record in line {}: `{}`", n + 1, record);
+            let mut fields = record.trim().splitn(4, ' ');
+
+            match fields.next() {
+                Some("symbol") => {
+                    let address = fields.next().and_then(parse_hex).ok_or_else(invalid)?;
+                    let name = fields.next().ok_or_else(invalid)?;
+                    info.symbols.push(SymbolInfo {
+                        name: name.to_string(),
+                        address,
+                    });
+                }
+                Some("line") => {
+                    let address = fields.next().and_then(parse_hex).ok_or_else(invalid)?;
+                    let size = fields.next().and_then(|s| s.parse().ok());
+                    let location = fields.next().and_then(|l| l.rsplit_once(':'));
+                    let line = location.and_then(|(_, line)| line.parse().ok());
+                    match (size, location, line) {
+                        (Some(size), Some((file, _)), Some(line)) => info.lines.push(LineInfo {
+                            address,
+                            size,
+                            file: file.to_string(),
+                            line,
+                        }),
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("") => {}
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+fn parse_hex(s: &str) -> Option<Word> {
+    Word::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+}
+
+impl fmt::Display for DebugInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", DEBUG_INFO_MAGIC, DEBUG_INFO_VERSION)?;
+        for s in &self.symbols {
+            writeln!(f, "symbol 0x{:08X} {}", s.address, s.name)?;
+        }
+        for l in &self.lines {
+            writeln!(
+                f,
+                "line 0x{:08X} {} {}:{}",
+                l.address, l.size, l.file, l.line
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{decode, register_name, Byte, DebugInfo, Decoded, OperandKind, Word};
+
+/// Data bytes per `.byte` line.
+const BYTES_PER_LINE: usize = 8;
+
+impl Decoded {
+    /// Renders the instruction in assembler syntax. `label` names the targets of address
+    /// operands, the others are shown as numbers.
+    pub fn to_assembly(&self, label: impl Fn(Word) -> Option<String>) -> String {
+        let operands: Vec<String> = self
+            .instruction
+            .operands
+            .iter()
+            .zip(&self.operands)
+            .map(|(kind, &value)| match kind {
+                OperandKind::Register => register_name(value)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("0x{:X}", value)),
+                OperandKind::Address => label(value)
+                    .map(|name| format!(":{}", name))
+                    .unwrap_or_else(|| format!("0x{:08X}", value)),
+                OperandKind::Immediate => format!("0x{:X}", value),
+                OperandKind::Flag => value.to_string(),
+            })
+            .collect();
+
+        if operands.is_empty() {
+            self.instruction.mnemonic.to_string()
+        } else {
+            format!("{} {}", self.instruction.mnemonic, operands.join(", "))
+        }
+    }
+}
+
+/// An instruction or a run of bytes that are no valid instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisassembledLine {
+    pub address: Word,
+    pub bytes: Vec<Byte>,
+    /// Label defined at the address
+    pub label: Option<String>,
+    /// The instruction or the `.byte` directive
+    pub text: String,
+}
+
+/// A disassembled image, printed as source the assembler accepts again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disassembly {
+    pub base: Word,
+    pub lines: Vec<DisassembledLine>,
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.base != 0 {
+            writeln!(f, ".org 0x{:X}", self.base)?;
+        }
+
+        for line in &self.lines {
+            if let Some(label) = &line.label {
+                writeln!(f, ":{}", label)?;
+            }
+            let bytes: Vec<String> = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            writeln!(
+                f,
+                "    {:<32}; {:08X}  {}",
+                line.text,
+                line.address,
+                bytes.join(" ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+enum Unit {
+    Instruction(Decoded),
+    Data(Byte),
+}
+
+/// Disassembles an image loaded at `base`. Labels come from `debug_info` where it has
+/// one, other targets of address operands inside the image get a label like `L_00000104`.
+/// Bytes that don't decode to an instruction become `.byte` lines, bytes that would be
+/// past the end of the address space are left out.
+pub fn disassemble(image: &[Byte], base: Word, debug_info: &DebugInfo) -> Disassembly {
+    let fitting = (1u64 << 32) - base as u64;
+    let image = &image[..image.len().min(fitting as usize)];
+    let end = base as u64 + image.len() as u64;
+    let inside = |address: Word| address >= base && (address as u64) < end;
+
+    // known labels, instructions are not decoded across them. Inserting in reverse keeps
+    // the first of several labels at the same address.
+    let mut labels: BTreeMap<Word, String> = BTreeMap::new();
+    for symbol in debug_info.symbols.iter().rev() {
+        if inside(symbol.address) {
+            labels.insert(symbol.address, symbol.name.clone());
+        }
+    }
+
+    let mut units = Vec::new();
+    let mut offset = 0;
+    while offset < image.len() {
+        let address = base + offset as Word;
+        let unit = match decode(&image[offset..]) {
+            // the last byte of the instruction is inside the image, its address can't overflow
+            Ok(decoded)
+                if !labels
+                    .range(address..=address + (decoded.size() - 1) as Word)
+                    .any(|(&label, _)| label != address) =>
+            {
+                Unit::Instruction(decoded)
+            }
+            _ => Unit::Data(image[offset]),
+        };
+        let size = match &unit {
+            Unit::Instruction(decoded) => decoded.size(),
+            Unit::Data(_) => 1,
+        };
+
+        units.push((address, unit));
+        offset += size;
+    }
+
+    // targets that start a unit get a label, others would not assemble again
+    let starts: Vec<Word> = units.iter().map(|(address, _)| *address).collect();
+    for (_, unit) in &units {
+        if let Unit::Instruction(decoded) = unit {
+            let targets = decoded.instruction.operands.iter().zip(&decoded.operands);
+            for (_, &target) in targets.filter(|(kind, _)| **kind == OperandKind::Address) {
+                if starts.binary_search(&target).is_ok() {
+                    labels
+                        .entry(target)
+                        .or_insert_with(|| format!("L_{:08X}", target));
+                }
+            }
+        }
+    }
+
+    let mut lines: Vec<DisassembledLine> = Vec::new();
+    let mut data_run = false;
+    for (address, unit) in units {
+        let label = labels.get(&address).cloned();
+        match unit {
+            Unit::Instruction(decoded) => {
+                let start = (address - base) as usize;
+                lines.push(DisassembledLine {
+                    address,
+                    bytes: image[start..start + decoded.size()].to_vec(),
+                    label,
+                    text: decoded.to_assembly(|target| labels.get(&target).cloned()),
+                });
+                data_run = false;
+            }
+            Unit::Data(byte) => {
+                match lines.last_mut() {
+                    Some(line)
+                        if data_run && label.is_none() && line.bytes.len() < BYTES_PER_LINE =>
+                    {
+                        line.bytes.push(byte);
+                        line.text.push_str(&format!(", 0x{:02X}", byte));
+                    }
+                    _ => lines.push(DisassembledLine {
+                        address,
+                        bytes: vec![byte],
+                        label,
+                        text: format!(".byte 0x{:02X}", byte),
+                    }),
+                }
+                data_run = true;
+            }
+        }
+    }
+
+    Disassembly { base, lines }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use crate::{from_opcode, Byte, Instruction, Word};
+
+impl Instruction {
+    /// Appends the opcode and the operands, each little endian with the width of its kind.
+    ///
+    /// Operands wider than their kind are truncated, callers are expected to range check them.
+    pub fn encode(&self, operands: &[Word], out: &mut Vec<Byte>) {
+        assert_eq!(
+            operands.len(),
+            self.operands.len(),
+            "[ISA] {} expects {} operands",
+            self.mnemonic,
+            self.operands.len()
+        );
+
+        out.push(self.opcode);
+        for (kind, value) in self.operands.iter().zip(operands) {
+            out.extend_from_slice(&value.to_le_bytes()[..kind.width()]);
+        }
+    }
+}
+
+/// An instruction together with its operand values.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Decoded {
+    pub instruction: &'static Instruction,
+    pub operands: Vec<Word>,
+}
+
+impl Decoded {
+    /// Number of bytes the instruction occupied.
+    pub fn size(&self) -> usize {
+        self.instruction.size()
+    }
+}
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.instruction.mnemonic)?;
+        for (i, (kind, value)) in self
+            .instruction
+            .operands
+            .iter()
+            .zip(&self.operands)
+            .enumerate()
+        {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(
+                f,
+                "{}0x{:0width$X}",
+                separator,
+                value,
+                width = kind.width() * 2
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// There are no bytes left to decode
+    Empty,
+    UnknownOpcode(Byte),
+    /// The bytes end before all operands of the instruction
+    Truncated(&'static Instruction),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "No instruction to decode"),
+            DecodeError::UnknownOpcode(opcode) => {
+                write!(f, "No such instruction: '0x{:02X}'", opcode)
+            }
+            DecodeError::Truncated(i) => write!(f, "Truncated instruction '{}'", i.mnemonic),
+        }
+    }
+}
+
+/// Decodes the instruction at the start of `bytes`.
+pub fn decode(bytes: &[Byte]) -> Result<Decoded, DecodeError> {
+    let opcode = *bytes.first().ok_or(DecodeError::Empty)?;
+    let instruction = from_opcode(opcode).ok_or(DecodeError::UnknownOpcode(opcode))?;
+
+    if bytes.len() < instruction.size() {
+        return Err(DecodeError::Truncated(instruction));
+    }
+
+    let mut operands = Vec::with_capacity(instruction.operands.len());
+    let mut offset = 1;
+    for kind in instruction.operands {
+        let mut value = [0; 4];
+        value[..kind.width()].copy_from_slice(&bytes[offset..offset + kind.width()]);
+        operands.push(Word::from_le_bytes(value));
+        offset += kind.width();
+    }
+
+    Ok(Decoded {
+        instruction,
+        operands,
+    })
+}
@@ -0,0 +1,184 @@
+use std::convert::TryInto;
+use std::path::Path;
+
+use crate::{Byte, Word};
+
+/// Magic bytes at the start of every executable, followed by the format and ISA version.
+pub const EXECUTABLE_MAGIC: &[u8; 4] = b"0xEX";
+pub const EXECUTABLE_VERSION: u16 = 1;
+
+/// Memory mapped devices a program expects, they decide where RAM starts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Devices {
+    pub screen: bool,
+    pub hard_drive: bool,
+}
+
+impl Devices {
+    fn to_bits(self) -> Word {
+        self.screen as Word | (self.hard_drive as Word) << 1
+    }
+
+    fn from_bits(bits: Word) -> Result<Devices, String> {
+        if bits & !0b11 != 0 {
+            return Err(format!("unknown devices 0x{:X} in executable", bits));
+        }
+
+        Ok(Devices {
+            screen: bits & 1 != 0,
+            hard_drive: bits & 2 != 0,
+        })
+    }
+}
+
+/// A part of the program copied to its address, followed by zeroed space.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub address: Word,
+    pub data: Vec<Byte>,
+    /// Zeroed bytes after the data, for `.bss` sections
+    pub bss_size: Word,
+}
+
+impl Segment {
+    /// Bytes the segment occupies in memory, segments read from a file may not fit into
+    /// the address space.
+    pub fn size(&self) -> u64 {
+        self.data.len() as u64 + self.bss_size as u64
+    }
+}
+
+/// Program file with a header, written by `asm --format exe` and loaded by the VM.
+///
+/// All values are little endian:
+/// ```text
+/// magic "0xEX", format version: u16, ISA version: u16
+/// entry: u32, load address: u32, devices: u32
+/// segment count: u32, then per segment: address: u32, data size: u32, bss size: u32, data
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Executable {
+    /// Address execution starts at
+    pub entry: Word,
+    /// Lowest address of the program, the VM's RAM has to start at or before it
+    pub load_address: Word,
+    pub devices: Devices,
+    pub segments: Vec<Segment>,
+}
+
+fn take<'a>(bytes: &mut &'a [Byte], count: usize) -> Result<&'a [Byte], String> {
+    if bytes.len() < count {
+        return Err("unexpected end of executable".to_string());
+    }
+    let (taken, rest) = bytes.split_at(count);
+    *bytes = rest;
+
+    Ok(taken)
+}
+
+fn half(bytes: &mut &[Byte]) -> Result<u16, String> {
+    Ok(u16::from_le_bytes(take(bytes, 2)?.try_into().unwrap()))
+}
+
+fn word(bytes: &mut &[Byte]) -> Result<Word, String> {
+    Ok(Word::from_le_bytes(take(bytes, 4)?.try_into().unwrap()))
+}
+
+impl Executable {
+    /// Reads a program file of any format. Intel HEX and S-records are recognized by their
+    /// extension (`.hex`, `.ihex`, `.srec`, `.s19`, `.s28`, `.s37`, `.mot`), executables by
+    /// their magic bytes. Returns `None` for flat binaries.
+    pub fn from_file(file_name: &str, bytes: &[Byte]) -> Result<Option<Executable>, String> {
+        let extension = Path::new(file_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let text = || std::str::from_utf8(bytes).map_err(|_| "not a text file".to_string());
+
+        match extension.as_deref() {
+            Some("hex") | Some("ihex") => Executable::from_intel_hex(text()?).map(Some),
+            Some("srec") | Some("s19") | Some("s28") | Some("s37") | Some("mot") => {
+                Executable::from_srecord(text()?).map(Some)
+            }
+            _ if Executable::is_executable(bytes) => Executable::from_bytes(bytes).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether the bytes start like an executable, anything else is a flat binary.
+    pub fn is_executable(bytes: &[Byte]) -> bool {
+        bytes.starts_with(EXECUTABLE_MAGIC)
+    }
+
+    pub fn to_bytes(&self) -> Vec<Byte> {
+        let mut bytes = EXECUTABLE_MAGIC.to_vec();
+        bytes.extend_from_slice(&EXECUTABLE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&crate::ISA_VERSION.to_le_bytes());
+
+        let header = [
+            self.entry,
+            self.load_address,
+            self.devices.to_bits(),
+            self.segments.len() as Word,
+        ];
+        for value in &header {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        for segment in &self.segments {
+            bytes.extend_from_slice(&segment.address.to_le_bytes());
+            bytes.extend_from_slice(&(segment.data.len() as Word).to_le_bytes());
+            bytes.extend_from_slice(&segment.bss_size.to_le_bytes());
+            bytes.extend_from_slice(&segment.data);
+        }
+
+        bytes
+    }
+
+    /// Reads an executable, rejecting other formats and versions.
+    pub fn from_bytes(mut bytes: &[Byte]) -> Result<Executable, String> {
+        let bytes = &mut bytes;
+        if take(bytes, EXECUTABLE_MAGIC.len()).ok() != Some(&EXECUTABLE_MAGIC[..]) {
+            return Err("not an executable".to_string());
+        }
+        let version = half(bytes)?;
+        if version != EXECUTABLE_VERSION {
+            return Err(format!("unsupported executable version {}", version));
+        }
+        let isa_version = half(bytes)?;
+        if isa_version != crate::ISA_VERSION {
+            return Err(format!(
+                "the executable was built for version {} of the instruction set, this is version {}",
+                isa_version,
+                crate::ISA_VERSION
+            ));
+        }
+
+        let entry = word(bytes)?;
+        let load_address = word(bytes)?;
+        let devices = Devices::from_bits(word(bytes)?)?;
+
+        let mut segments = Vec::new();
+        for _ in 0..word(bytes)? {
+            let address = word(bytes)?;
+            let size = word(bytes)? as usize;
+            let bss_size = word(bytes)?;
+            segments.push(Segment {
+                address,
+                data: take(bytes, size)?.to_vec(),
+                bss_size,
+            });
+        }
+
+        if !bytes.is_empty() {
+            return Err("unexpected bytes after the last segment".to_string());
+        }
+
+        Ok(Executable {
+            entry,
+            load_address,
+            devices,
+            segments,
+        })
+    }
+}
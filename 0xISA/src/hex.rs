@@ -0,0 +1,218 @@
+use crate::{Byte, Devices, Executable, Segment, Word};
+
+/// Data bytes per record, the common choice of existing tools.
+const BYTES_PER_RECORD: usize = 16;
+
+fn hex(bytes: &[Byte]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Parses the hex digits of a record, `line` is used in errors.
+fn parse_bytes(digits: &str, line: usize) -> Result<Vec<Byte>, String> {
+    if !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex digits in line {}", line));
+    }
+
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| Byte::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+fn big_endian(bytes: &[Byte]) -> Word {
+    bytes.iter().fold(0, |value, &b| value << 8 | b as Word)
+}
+
+/// Data records of the segments, split so that none crosses a multiple of `boundary`.
+fn chunks(executable: &Executable, boundary: u64) -> Vec<(Word, &[Byte])> {
+    let mut chunks = Vec::new();
+    for segment in &executable.segments {
+        let mut address = segment.address;
+        let mut data = &segment.data[..];
+        while !data.is_empty() {
+            let to_boundary = (boundary - address as u64 % boundary) as usize;
+            let (chunk, rest) = data.split_at(data.len().min(BYTES_PER_RECORD).min(to_boundary));
+            chunks.push((address, chunk));
+            address = address.wrapping_add(chunk.len() as Word);
+            data = rest;
+        }
+    }
+
+    chunks
+}
+
+/// Collects the data records into segments, merging records that continue each other.
+fn from_records(records: Vec<(Word, Vec<Byte>)>, entry: Option<Word>) -> Executable {
+    let mut segments: Vec<Segment> = Vec::new();
+    for (address, data) in records {
+        match segments.last_mut() {
+            Some(segment)
+                if segment.address as u64 + segment.data.len() as u64 == address as u64 =>
+            {
+                segment.data.extend(data)
+            }
+            _ => segments.push(Segment {
+                address,
+                data,
+                bss_size: 0,
+            }),
+        }
+    }
+
+    let load_address = segments.iter().map(|s| s.address).min().unwrap_or(0);
+    Executable {
+        entry: entry.unwrap_or(load_address),
+        load_address,
+        devices: Devices::default(),
+        segments,
+    }
+}
+
+impl Executable {
+    /// Writes the segments as Intel HEX with 32-bit addresses. The entry point is kept,
+    /// the devices and bss segments are not.
+    pub fn to_intel_hex(&self) -> String {
+        let record = |kind: Byte, address: u16, data: &[Byte]| {
+            let mut bytes = vec![data.len() as Byte];
+            bytes.extend_from_slice(&address.to_be_bytes());
+            bytes.push(kind);
+            bytes.extend_from_slice(data);
+            let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+            bytes.push(sum.wrapping_neg());
+            format!(":{}\n", hex(&bytes))
+        };
+
+        let mut text = String::new();
+        let mut upper = 0;
+        for (address, data) in chunks(self, 0x10000) {
+            // extended linear address whenever the upper 16 bits change
+            if address >> 16 != upper {
+                upper = address >> 16;
+                text.push_str(&record(0x04, 0, &(upper as u16).to_be_bytes()));
+            }
+            text.push_str(&record(0x00, address as u16, data));
+        }
+        text.push_str(&record(0x05, 0, &self.entry.to_be_bytes()));
+        text.push_str(&record(0x01, 0, &[]));
+
+        text
+    }
+
+    /// Reads Intel HEX, the load address is the lowest address of any data.
+    pub fn from_intel_hex(text: &str) -> Result<Executable, String> {
+        let mut records = Vec::new();
+        let mut entry = None;
+        let mut upper: Word = 0;
+
+        for (n, line) in text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+        {
+            let line_number = n + 1;
+            let digits = line
+                .trim()
+                .strip_prefix(':')
+                .ok_or_else(|| format!("expected `:` at the start of line {}", line_number))?;
+            let bytes = parse_bytes(digits, line_number)?;
+            if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+                return Err(format!("invalid record length in line {}", line_number));
+            }
+            if bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+                return Err(format!("wrong checksum in line {}", line_number));
+            }
+
+            let address = big_endian(&bytes[1..3]);
+            let data = &bytes[4..bytes.len() - 1];
+            match (bytes[3], data.len()) {
+                (0x00, _) => records.push((upper.wrapping_add(address), data.to_vec())),
+                (0x01, _) => return Ok(from_records(records, entry)),
+                (0x02, 2) => upper = big_endian(data) << 4,
+                (0x03, 4) => entry = Some((big_endian(&data[..2]) << 4) + big_endian(&data[2..])),
+                (0x04, 2) => upper = big_endian(data) << 16,
+                (0x05, 4) => entry = Some(big_endian(data)),
+                (kind, _) => {
+                    return Err(format!(
+                        "invalid record type {:02X} in line {}",
+                        kind, line_number
+                    ))
+                }
+            }
+        }
+
+        Err("missing end of file record".to_string())
+    }
+
+    /// Writes the segments as Motorola S-records with 32-bit addresses (S3 and S7). The
+    /// entry point is kept, the devices and bss segments are not.
+    pub fn to_srecord(&self) -> String {
+        let record = |kind: char, address: &[Byte], data: &[Byte]| {
+            let mut bytes = vec![(address.len() + data.len() + 1) as Byte];
+            bytes.extend_from_slice(address);
+            bytes.extend_from_slice(data);
+            let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+            bytes.push(!sum);
+            format!("S{}{}\n", kind, hex(&bytes))
+        };
+
+        // the header record has a 16-bit address
+        let mut text = record('0', &[0, 0], b"0xASM");
+        for (address, data) in chunks(self, 1 << 32) {
+            text.push_str(&record('3', &address.to_be_bytes(), data));
+        }
+        text.push_str(&record('7', &self.entry.to_be_bytes(), &[]));
+
+        text
+    }
+
+    /// Reads Motorola S-records, the load address is the lowest address of any data.
+    pub fn from_srecord(text: &str) -> Result<Executable, String> {
+        let mut records = Vec::new();
+
+        for (n, line) in text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+        {
+            let line_number = n + 1;
+            let line = line.trim();
+            let kind = match line.strip_prefix('S').and_then(|l| l.chars().next()) {
+                Some(kind) => kind,
+                None => return Err(format!("expected `S` at the start of line {}", line_number)),
+            };
+            let address_width = match kind {
+                '0' | '1' | '5' | '9' => 2,
+                '2' | '6' | '8' => 3,
+                '3' | '7' => 4,
+                _ => {
+                    return Err(format!(
+                        "invalid record type S{} in line {}",
+                        kind, line_number
+                    ))
+                }
+            };
+
+            let bytes = parse_bytes(&line[2..], line_number)?;
+            if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+                return Err(format!("invalid record length in line {}", line_number));
+            }
+            if bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0xFF {
+                return Err(format!("wrong checksum in line {}", line_number));
+            }
+            if bytes.len() < address_width + 2 {
+                return Err(format!("invalid record length in line {}", line_number));
+            }
+
+            let address = big_endian(&bytes[1..1 + address_width]);
+            let data = &bytes[1 + address_width..bytes.len() - 1];
+            match kind {
+                '1' | '2' | '3' => records.push((address, data.to_vec())),
+                '7' | '8' | '9' => return Ok(from_records(records, Some(address))),
+                // header and record counts
+                _ => {}
+            }
+        }
+
+        Err("missing termination record".to_string())
+    }
+}
@@ -0,0 +1,188 @@
+use crate::Byte;
+
+/// Calls `$callback!` with the definition of every instruction as
+/// `(MNEMONIC, opcode, [operand kinds])`, optionally prefixed by extra tokens.
+///
+/// This table is the single source of truth for the instruction set.
+#[macro_export]
+macro_rules! for_each_instruction {
+    ($callback:ident $(, $arg:tt)*) => {
+        $callback! {
+            $($arg,)*
+
+            // Control
+            (HALT, 0xFF, []),
+            (NOP, 0x00, []),
+
+            // Move
+            (MOVR, 0x10, [Immediate, Register]),
+            (MOVM, 0x11, [Immediate, Address]),
+            (MOVRR, 0x12, [Register, Register]),
+            (MOVRM, 0x13, [Register, Address]),
+            (MOVMR, 0x14, [Address, Register]),
+            (MOVRPR, 0x17, [Register, Register]),
+            (MOVROR, 0x18, [Register, Immediate, Register]),
+            (LOAD, 0x19, [Register, Register, Address]),
+            (LOADR, 0x1A, [Register, Register, Register]),
+            (LOADM, 0x1B, [Register, Register, Address]),
+            (STORE, 0x1C, [Address, Register, Register]),
+            (STORER, 0x1D, [Register, Register, Register]),
+            (STOREM, 0x1E, [Address, Register, Register]),
+
+            // Stack
+            (POP, 0x05, [Register]),
+            (PUSH, 0x15, [Immediate]),
+            (PUSHR, 0x16, [Register]),
+
+            // Subroutines
+            (JMP, 0x01, [Address]),
+            (CALL, 0x02, [Address]),
+            (CALLR, 0x03, [Register]),
+            (RET, 0x04, []),
+
+            // Arithmetic
+            (ADD, 0x20, [Immediate, Register]),
+            (ADDR, 0x21, [Register, Register]),
+            (SUB, 0x22, [Register, Immediate]),
+            (SUBWR, 0x23, [Immediate, Register]),
+            (SUBR, 0x24, [Register, Register]),
+            (MULT, 0x25, [Immediate, Register]),
+            (MULTR, 0x26, [Register, Register]),
+            (DIV, 0x27, [Register, Immediate]),
+            (DIVWR, 0x28, [Immediate, Register]),
+            (DIVR, 0x29, [Register, Register]),
+            (INC, 0x2A, [Register]),
+            (DEC, 0x2B, [Register]),
+
+            // Bitwise
+            (LSF, 0x50, [Register, Immediate]),
+            (LSFR, 0x51, [Register, Register]),
+            (RSF, 0x52, [Register, Immediate]),
+            (RSFR, 0x53, [Register, Register]),
+            (WLSF, 0x54, [Register, Immediate]),
+            (WLSFR, 0x55, [Register, Register]),
+            (WRSF, 0x56, [Register, Immediate]),
+            (WRSFR, 0x57, [Register, Register]),
+            (AND, 0x58, [Register, Immediate]),
+            (ANDR, 0x59, [Register, Register]),
+            (OR, 0x5A, [Register, Immediate]),
+            (ORR, 0x5B, [Register, Register]),
+            (XOR, 0x5C, [Register, Immediate]),
+            (XORR, 0x5D, [Register, Register]),
+            (NOT, 0x5E, [Register]),
+
+            // Conditional jumps
+            (BRBS, 0x30, [Flag, Address]),
+            (BRBC, 0x31, [Flag, Address]),
+            (BREQ, 0x32, [Immediate, Address]),
+            (BREQR, 0x33, [Register, Address]),
+            (BREQRW, 0x34, [Register, Immediate, Address]),
+            (BREQRR, 0x35, [Register, Register, Address]),
+            (BRNQ, 0x36, [Immediate, Address]),
+            (BRNQR, 0x37, [Register, Address]),
+            (BRNQRW, 0x38, [Register, Immediate, Address]),
+            (BRNQRR, 0x39, [Register, Register, Address]),
+            (BRLT, 0x3A, [Immediate, Address]),
+            (BRLTR, 0x3B, [Register, Address]),
+            (BRLTRW, 0x3C, [Register, Immediate, Address]),
+            (BRLTRR, 0x3D, [Register, Register, Address]),
+            (BRGT, 0x3E, [Immediate, Address]),
+            (BRGTR, 0x3F, [Register, Address]),
+            (BRGTRW, 0x40, [Register, Immediate, Address]),
+            (BRGTRR, 0x41, [Register, Register, Address]),
+            (BRLTE, 0x42, [Immediate, Address]),
+            (BRLTER, 0x43, [Register, Address]),
+            (BRLTERW, 0x44, [Register, Immediate, Address]),
+            (BRLTERR, 0x45, [Register, Register, Address]),
+            (BRGTE, 0x46, [Immediate, Address]),
+            (BRGTER, 0x47, [Register, Address]),
+            (BRGTERW, 0x48, [Register, Immediate, Address]),
+            (BRGTERR, 0x49, [Register, Register, Address]),
+        }
+    };
+}
+
+/// Highest number of operands any instruction takes
+pub const MAX_OPERANDS: usize = 3;
+
+/// Version of the instruction table, executables built for another version are rejected
+pub const ISA_VERSION: u16 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperandKind {
+    /// 32-Bit literal value
+    Immediate,
+    /// Offset of a register inside the register file
+    Register,
+    /// Absolute memory address
+    Address,
+    /// Index of a bit in the status register
+    Flag,
+}
+
+impl OperandKind {
+    /// Number of bytes the operand occupies in the encoded instruction.
+    pub fn width(self) -> usize {
+        match self {
+            OperandKind::Flag => 1,
+            _ => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OperandKind::Immediate => "immediate",
+            OperandKind::Register => "register",
+            OperandKind::Address => "address",
+            OperandKind::Flag => "status bit",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub mnemonic: &'static str,
+    pub opcode: Byte,
+    pub operands: &'static [OperandKind],
+}
+
+impl Instruction {
+    /// Number of bytes the encoded instruction occupies, including the opcode.
+    pub fn size(&self) -> usize {
+        1 + self.operands.iter().map(|k| k.width()).sum::<usize>()
+    }
+}
+
+macro_rules! define_instructions {
+    ($(($mnemonic:ident, $opcode:literal, [$($kind:ident),*])),* $(,)?) => {
+        /// Opcode of every instruction
+        pub mod opcodes {
+            use crate::Byte;
+
+            $(pub const $mnemonic: Byte = $opcode;)*
+        }
+
+        /// Every instruction of the instruction set
+        pub const INSTRUCTIONS: &[Instruction] = &[
+            $(Instruction {
+                mnemonic: stringify!($mnemonic),
+                opcode: $opcode,
+                operands: &[$(OperandKind::$kind),*],
+            },)*
+        ];
+    };
+}
+
+for_each_instruction!(define_instructions);
+
+/// Looks up an instruction by its mnemonic, ignoring case.
+pub fn from_mnemonic(mnemonic: &str) -> Option<&'static Instruction> {
+    INSTRUCTIONS
+        .iter()
+        .find(|i| i.mnemonic.eq_ignore_ascii_case(mnemonic))
+}
+
+/// Looks up an instruction by its opcode.
+pub fn from_opcode(opcode: Byte) -> Option<&'static Instruction> {
+    INSTRUCTIONS.iter().find(|i| i.opcode == opcode)
+}
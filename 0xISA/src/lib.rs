@@ -0,0 +1,24 @@
+//! Instruction set of the 0xVM, shared by the VM and the assembler.
+
+pub type Byte = u8;
+pub type Word = u32;
+
+mod instructions;
+pub use instructions::*;
+
+mod encoding;
+pub use encoding::*;
+
+mod registers;
+pub use registers::*;
+
+mod debug_info;
+pub use debug_info::*;
+
+mod disassembler;
+pub use disassembler::*;
+
+mod executable;
+pub use executable::*;
+
+mod hex;
@@ -0,0 +1,44 @@
+use crate::Word;
+
+/// Calls `$callback!` with the names of all registers in the order of the register file.
+///
+/// Every register is a word wide, so the offset of a register is its index times 4.
+#[macro_export]
+macro_rules! for_each_register {
+    ($callback:ident) => {
+        $callback![
+            "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8",  // general purpose registers
+            "pc",  // program counter
+            "acc", // accumulator
+            "sr",  // status register
+            "sp",  // stack pointer
+            "fp",  // frame pointer
+        ];
+    };
+}
+
+macro_rules! define_registers {
+    ($($name:literal),* $(,)?) => {
+        /// Names of all registers in the order of the register file
+        pub const REGISTERS: &[&str] = &[$($name),*];
+    };
+}
+
+for_each_register!(define_registers);
+
+/// Returns the offset of a register inside the register file, ignoring case.
+pub fn register_offset(name: &str) -> Option<Word> {
+    REGISTERS
+        .iter()
+        .position(|r| r.eq_ignore_ascii_case(name))
+        .map(|i| i as Word * 4)
+}
+
+/// Returns the name of the register at `offset`.
+pub fn register_name(offset: Word) -> Option<&'static str> {
+    if !offset.is_multiple_of(4) {
+        return None;
+    }
+
+    REGISTERS.get(offset as usize / 4).copied()
+}
@@ -0,0 +1,53 @@
+use isa::{DebugInfo, LineInfo, SymbolInfo};
+
+fn info() -> DebugInfo {
+    DebugInfo {
+        lines: vec![LineInfo {
+            address: 0x110,
+            size: 9,
+            file: "lib.asm".to_string(),
+            line: 42,
+        }],
+        symbols: vec![
+            SymbolInfo {
+                name: "start".to_string(),
+                address: 0x100,
+            },
+            SymbolInfo {
+                name: "print_string".to_string(),
+                address: 0x104,
+            },
+        ],
+    }
+}
+
+#[test]
+fn describe_uses_the_closest_label_and_the_line() {
+    let info = info();
+
+    assert_eq!(info.describe(0x104), "print_string");
+    assert_eq!(info.describe(0x110), "print_string+0x0C (lib.asm:42)");
+    assert_eq!(info.describe(0x119), "print_string+0x15");
+    assert_eq!(info.describe(0xFF), "0x000000FF");
+}
+
+#[test]
+fn text_roundtrip() {
+    let info = info();
+    let text = info.to_string();
+
+    assert!(text.starts_with("0xDBG 1\n"));
+    assert!(text.contains("line 0x00000110 9 lib.asm:42\n"));
+    assert_eq!(DebugInfo::parse(&text), Ok(info));
+}
+
+#[test]
+fn parse_errors() {
+    assert!(DebugInfo::parse("hello")
+        .unwrap_err()
+        .starts_with("not a debug info file"));
+    assert_eq!(
+        DebugInfo::parse("0xDBG 1\nline 0x10 lib.asm:4"),
+        Err("invalid record in line 2: `line 0x10 lib.asm:4`".to_string())
+    );
+}
@@ -0,0 +1,76 @@
+use isa::{disassemble, from_mnemonic, DebugInfo, SymbolInfo};
+
+fn encode(program: &[(&str, &[u32])]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (mnemonic, operands) in program {
+        from_mnemonic(mnemonic)
+            .unwrap()
+            .encode(operands, &mut bytes);
+    }
+    bytes
+}
+
+#[test]
+fn registers_by_name_and_synthetic_labels() {
+    // MOVR is 9 bytes, so the loop starts at 0x109
+    let image = encode(&[
+        ("MOVR", &[0x41, 0]),
+        ("INC", &[0x24]),
+        ("BRNQ", &[0x10, 0x109]),
+        ("HALT", &[]),
+    ]);
+    let text = disassemble(&image, 0x100, &DebugInfo::default()).to_string();
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|l| l.split(';').next().unwrap().trim_end())
+        .collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            ".org 0x100",
+            "    MOVR 0x41, r1",
+            ":L_00000109",
+            "    INC acc",
+            "    BRNQ 0x10, :L_00000109",
+            "    HALT",
+        ]
+    );
+}
+
+#[test]
+fn symbols_name_targets_and_split_data() {
+    let mut image = encode(&[("JMP", &[0x7]), ("HALT", &[])]);
+    // the label starts in what would be the operand of an instruction
+    image.extend_from_slice(&[0x10, 0xEE, 0xEE]);
+    let info = DebugInfo {
+        lines: Vec::new(),
+        symbols: vec![SymbolInfo {
+            name: "data".to_string(),
+            address: 0x7,
+        }],
+    };
+    let disassembly = disassemble(&image, 0, &info);
+    let texts: Vec<&str> = disassembly.lines.iter().map(|l| l.text.as_str()).collect();
+
+    assert_eq!(
+        texts,
+        vec!["JMP :data", "HALT", ".byte 0x10", ".byte 0xEE, 0xEE"]
+    );
+    assert_eq!(disassembly.lines[3].label, Some("data".to_string()));
+}
+
+#[test]
+fn images_at_the_end_of_the_address_space() {
+    let image = encode(&[("JMP", &[0xFFFF_FFFE]), ("HALT", &[]), ("HALT", &[])]);
+
+    // the last byte is at 0xFFFFFFFF
+    let disassembly = disassemble(&image, 0xFFFF_FFF9, &DebugInfo::default());
+    let texts: Vec<&str> = disassembly.lines.iter().map(|l| l.text.as_str()).collect();
+    assert_eq!(texts, vec!["JMP :L_FFFFFFFE", "HALT", "HALT"]);
+
+    // bytes past it are left out
+    let disassembly = disassemble(&image, 0xFFFF_FFFB, &DebugInfo::default());
+    let texts: Vec<&str> = disassembly.lines.iter().map(|l| l.text.as_str()).collect();
+    assert_eq!(texts, vec!["JMP 0xFFFFFFFE"]);
+}
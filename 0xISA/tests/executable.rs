@@ -0,0 +1,59 @@
+use isa::{Devices, Executable, Segment, ISA_VERSION};
+
+fn executable() -> Executable {
+    Executable {
+        entry: 0x108,
+        load_address: 0x104,
+        devices: Devices {
+            screen: true,
+            hard_drive: false,
+        },
+        segments: vec![
+            Segment {
+                address: 0x104,
+                data: vec![0x00, 0x00, 0x00, 0x00, 0xFF],
+                bss_size: 0,
+            },
+            Segment {
+                address: 0x200,
+                data: Vec::new(),
+                bss_size: 512,
+            },
+        ],
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let bytes = executable().to_bytes();
+
+    assert!(Executable::is_executable(&bytes));
+    assert_eq!(Executable::from_bytes(&bytes), Ok(executable()));
+}
+
+#[test]
+fn rejects_other_isa_versions_and_truncated_files() {
+    let mut bytes = executable().to_bytes();
+    bytes[6..8].copy_from_slice(&(ISA_VERSION + 1).to_le_bytes());
+    assert!(Executable::from_bytes(&bytes)
+        .unwrap_err()
+        .starts_with("the executable was built for version"));
+
+    let bytes = executable().to_bytes();
+    assert_eq!(
+        Executable::from_bytes(&bytes[..bytes.len() - 1]),
+        Err("unexpected end of executable".to_string())
+    );
+    assert!(!Executable::is_executable(&[0xFF, 0x00]));
+}
+
+#[test]
+fn segment_sizes_past_the_address_space() {
+    let segment = Segment {
+        address: 0x104,
+        data: vec![0xFF; 4],
+        bss_size: u32::MAX,
+    };
+
+    assert_eq!(segment.size(), 0x1_0000_0003);
+}
@@ -0,0 +1,69 @@
+use isa::{Devices, Executable, Segment};
+
+fn executable() -> Executable {
+    Executable {
+        entry: 0x1_0004,
+        load_address: 0xFFF8,
+        devices: Devices::default(),
+        segments: vec![
+            // crosses the 64K boundary of Intel HEX addresses
+            Segment {
+                address: 0xFFF8,
+                data: (0..20).collect(),
+                bss_size: 0,
+            },
+            Segment {
+                address: 0x2_0000,
+                data: vec![0xFF],
+                bss_size: 0,
+            },
+        ],
+    }
+}
+
+#[test]
+fn intel_hex_roundtrip() {
+    let text = executable().to_intel_hex();
+
+    assert!(text.starts_with(":08FFF8000001020304050607E5\n:020000040001F9\n"));
+    assert!(text.ends_with(":0400000500010004F2\n:00000001FF\n"));
+    assert_eq!(Executable::from_intel_hex(&text), Ok(executable()));
+}
+
+#[test]
+fn srecord_roundtrip() {
+    let text = executable().to_srecord();
+
+    assert!(text.starts_with("S0080000307841534D6E\n"));
+    assert!(text.ends_with("S70500010004F5\n"));
+    assert_eq!(Executable::from_srecord(&text), Ok(executable()));
+}
+
+#[test]
+fn reads_records_of_other_tools() {
+    let hex =
+        Executable::from_intel_hex(":10010000214601360121470136007EFE09D2190140\n:00000001FF\n")
+            .unwrap();
+    assert_eq!(hex.load_address, 0x100);
+    assert_eq!(hex.entry, 0x100);
+    assert_eq!(hex.segments[0].data[..4], [0x21, 0x46, 0x01, 0x36]);
+
+    let srecord =
+        Executable::from_srecord("S1137AF00A0A0D0000000000000000000000000061\nS9030000FC\n")
+            .unwrap();
+    assert_eq!(srecord.load_address, 0x7AF0);
+    assert_eq!(srecord.entry, 0);
+    assert_eq!(srecord.segments[0].data.len(), 16);
+}
+
+#[test]
+fn checksum_errors() {
+    assert_eq!(
+        Executable::from_intel_hex(":0100000000FE\n:00000001FF"),
+        Err("wrong checksum in line 1".to_string())
+    );
+    assert_eq!(
+        Executable::from_srecord("S1040000FF00\n"),
+        Err("wrong checksum in line 1".to_string())
+    );
+}
@@ -0,0 +1,47 @@
+use isa::{decode, from_mnemonic, from_opcode, opcodes, DecodeError, INSTRUCTIONS, MAX_OPERANDS};
+
+#[test]
+fn opcodes_are_unique() {
+    for (i, a) in INSTRUCTIONS.iter().enumerate() {
+        assert!(a.operands.len() <= MAX_OPERANDS);
+
+        for b in &INSTRUCTIONS[i + 1..] {
+            assert_ne!(a.opcode, b.opcode, "{} and {}", a.mnemonic, b.mnemonic);
+        }
+    }
+}
+
+#[test]
+fn flag_operands_are_one_byte() {
+    let brbs = from_mnemonic("brbs").unwrap();
+
+    assert_eq!(brbs.opcode, opcodes::BRBS);
+    assert_eq!(brbs.size(), 6);
+}
+
+#[test]
+fn encode_decode_roundtrip() {
+    for instruction in INSTRUCTIONS {
+        let operands: Vec<u32> = (1..=instruction.operands.len() as u32).collect();
+        let mut bytes = Vec::new();
+        instruction.encode(&operands, &mut bytes);
+
+        assert_eq!(bytes.len(), instruction.size());
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(
+            decoded.instruction,
+            from_opcode(instruction.opcode).unwrap()
+        );
+        assert_eq!(decoded.operands, operands);
+    }
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(decode(&[0xEE]), Err(DecodeError::UnknownOpcode(0xEE)));
+    assert_eq!(
+        decode(&[opcodes::JMP, 0, 0]),
+        Err(DecodeError::Truncated(from_mnemonic("JMP").unwrap()))
+    );
+}
@@ -0,0 +1,18 @@
+use isa::{register_name, register_offset, REGISTERS};
+
+#[test]
+fn register_offsets() {
+    assert_eq!(register_offset("r1"), Some(0));
+    assert_eq!(register_offset("R8"), Some(28));
+    assert_eq!(register_offset("acc"), Some(36));
+    assert_eq!(register_offset("fp"), Some(48));
+    assert_eq!(register_offset("r9"), None);
+}
+
+#[test]
+fn register_names() {
+    for (i, name) in REGISTERS.iter().enumerate() {
+        assert_eq!(register_name(i as u32 * 4), Some(*name));
+    }
+    assert_eq!(register_name(2), None);
+}
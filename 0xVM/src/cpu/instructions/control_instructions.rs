@@ -0,0 +1,15 @@
+use crate::cpu::VM;
+
+/// ## HALT
+/// Stop the execution
+#[inline]
+#[allow(non_snake_case)]
+pub fn HALT(cpu: &mut VM) {
+    cpu.halt();
+}
+
+/// ## NOP
+/// Do nothing
+#[inline]
+#[allow(non_snake_case)]
+pub fn NOP(_: &mut VM) {}
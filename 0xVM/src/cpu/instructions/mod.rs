@@ -1,6 +1,7 @@
-mod move_instructions; pub use move_instructions::*;
-mod stack_instructions; pub use stack_instructions::*;
-mod arithmetic_instructions; pub use arithmetic_instructions::*;
-mod branch_instructions; pub use branch_instructions::*;
-mod subroutine_instructions; pub use subroutine_instructions::*;
-mod bitwise_instructions; pub use bitwise_instructions::*;
+mod control_instructions; pub use control_instructions::*;
+mod move_instructions; pub use move_instructions::*;
+mod stack_instructions; pub use stack_instructions::*;
+mod arithmetic_instructions; pub use arithmetic_instructions::*;
+mod branch_instructions; pub use branch_instructions::*;
+mod subroutine_instructions; pub use subroutine_instructions::*;
+mod bitwise_instructions; pub use bitwise_instructions::*;
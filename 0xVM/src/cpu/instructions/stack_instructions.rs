@@ -1,32 +1,32 @@
-use crate::cpu::VM;
-
-/// ## POP r1
-/// Pop val from stack into register r1
-#[inline]
-#[allow(non_snake_case)]
-pub fn POP(cpu: &mut VM) {
-    let r_addr = cpu.fetch_word();
-    let val = cpu.pop();
-    cpu.set_reg(r_addr, val);
-}
-
-/// ## PUSH 0x1234 
-/// Push 0x1234 onto the stack
-#[inline]
-#[allow(non_snake_case)]
-pub fn PUSH(cpu: &mut VM) {
-    let val = cpu.fetch_word();
-
-    cpu.push(val);
-}
-
-/// ## PUSHR r1 
-/// Push register r1 onto stack
-#[inline]
-#[allow(non_snake_case)]
-pub fn PUSHR(cpu: &mut VM) {
-	let r_addr = cpu.fetch_word();
-	let val = cpu.get_reg(r_addr);
-
-	cpu.push(val);
+use crate::cpu::VM;
+
+/// ## POP r1
+/// Pop val from stack into register r1
+#[inline]
+#[allow(non_snake_case)]
+pub fn POP(cpu: &mut VM) {
+    let r_addr = cpu.operand();
+    let val = cpu.pop();
+    cpu.set_reg(r_addr, val);
+}
+
+/// ## PUSH 0x1234 
+/// Push 0x1234 onto the stack
+#[inline]
+#[allow(non_snake_case)]
+pub fn PUSH(cpu: &mut VM) {
+    let val = cpu.operand();
+
+    cpu.push(val);
+}
+
+/// ## PUSHR r1 
+/// Push register r1 onto stack
+#[inline]
+#[allow(non_snake_case)]
+pub fn PUSHR(cpu: &mut VM) {
+	let r_addr = cpu.operand();
+	let val = cpu.get_reg(r_addr);
+
+	cpu.push(val);
 }
\ No newline at end of file
@@ -1,3 +1,3 @@
-mod vm_struct; pub use vm_struct::*;
-mod instructions; pub use instructions::*;
+mod vm_struct; pub use vm_struct::*;
+mod instructions;
 mod config_struct; pub use config_struct::*;
\ No newline at end of file
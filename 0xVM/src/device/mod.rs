@@ -1,3 +1,4 @@
-mod device; pub use device::*;
-mod screen; pub use screen::*;
+#[allow(clippy::module_inception)]
+mod device; pub use device::*;
+mod screen; pub use screen::*;
 mod hard_drive; pub use hard_drive::*;
\ No newline at end of file
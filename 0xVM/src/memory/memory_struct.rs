@@ -1,113 +1,108 @@
-use std::convert::TryInto;
-
-use crate::device::Device;
-
-pub type Word = u32;
-pub type HalfWord = u16;
-pub type Byte = u8;
-
-#[derive(Clone, PartialEq)]
-pub struct Memory {
-    data: Vec<Byte>,
-}
-
-#[allow(dead_code)]
-impl Memory {
-    pub fn new(size_in_bytes: Word) -> Self {
-        Memory {
-            data: vec![0; size_in_bytes as usize],
-        }
-    }
-
-    pub fn from(mut data: Vec<Byte>, total_size: Word) -> Self {
-        let buffer = if total_size > data.len() as Word {
-            total_size - data.len() as Word
-        } else {
-            0
-        };
-        
-        for _ in 0..buffer {
-            data.push(0);
-        }
-
-        Memory { data }
-    }
-
-    pub fn get_size(&self) -> Word {
-        self.data.len() as Word
-    }
-
-    /// Masks byte with bitwise-and at addr
-    pub fn and_set_byte(&mut self, addr: Word, mask: Byte) {
-        if self.data.len() < addr as usize {
-            panic!("[MEMORY] and_set_byte: No such addr '0x{:08X}'", addr);
-        }
-
-        self.data[addr as usize] &= mask;
-    }
-
-    /// Masks word with bitwise-or at addr
-    pub fn or_set_byte(&mut self, addr: Word, mask: Byte) {
-        if self.data.len() < addr as usize {
-            panic!("[MEMORY] or_set_byte: No such addr '0x{:08X}'", addr);
-        }
-
-        self.data[addr as usize] |= mask;
-    }
-}
-
-#[allow(dead_code)]
-impl Device for Memory {
-    fn get_byte(&self, addr: Word) -> Byte {
-        *self
-            .data
-            .get(addr as usize)
-            .expect(format!("[MEMORY] get_byte: No such addr '0x{:08X}'", addr).as_str())
-    }
-
-    fn get_word(&self, addr: Word) -> Word {
-        let data = self
-            .data
-            .get(addr as usize..addr as usize + 4)
-            .expect(format!("[MEMORY] get_word: No such addr '0x{:08X}'", addr).as_str())
-            .try_into()
-            .expect("[MEMORY] get_word: Oddly sized Word");
-
-        Word::from_le_bytes(data)
-    }
-
-    fn get_range(&self, addr: Word, size: Word) -> Vec<Byte> {
-        self.data
-            .get(addr as usize..addr as usize + size as usize)
-            .expect(format!("[MEMORY] get_range: No such addr range '0x{:08X}-{:08X}'", addr, addr + size).as_str())
-            .to_vec()
-    }
-
-    fn set_byte(&mut self, addr: Word, byte: Byte) {
-        if self.data.len() < addr as usize {
-            panic!("[MEMORY] set_byte: No such addr '0x{:08X}'", addr);
-        }
-
-        self.data[addr as usize] = byte;
-    }
-
-    fn set_word(&mut self, addr: Word, word: Word) {
-        if self.data.len() < addr as usize + 3 {
-            panic!("[MEMORY] set_word: No such addr '0x{:08X}'", addr);
-        }
-
-        for (i, byte) in word.to_le_bytes().iter().enumerate() {
-            self.data[addr as usize + i] = *byte;
-        }
-    }
-
-    fn set_range(&mut self, addr: Word, data: Vec<Byte>) {
-        if addr as usize + data.len() > self.data.len() {
-            panic!("[MEMORY] set_range: No such addr range '0x{:08X}-{:08X}'", addr, addr + data.len() as Word);
-        }
-
-        for (i, byte) in data.iter().enumerate() {
-            self.data[addr as usize + i] = *byte;
-        }
-    }
-}
+use std::convert::TryInto;
+
+use crate::device::Device;
+
+pub type Word = u32;
+pub type HalfWord = u16;
+pub type Byte = u8;
+
+#[derive(Clone, PartialEq)]
+pub struct Memory {
+    data: Vec<Byte>,
+}
+
+#[allow(dead_code)]
+impl Memory {
+    pub fn new(size_in_bytes: Word) -> Self {
+        Memory {
+            data: vec![0; size_in_bytes as usize],
+        }
+    }
+
+    pub fn from(mut data: Vec<Byte>, total_size: Word) -> Self {
+        // pad the data with zeros up to the total size
+        if total_size as usize > data.len() {
+            data.resize(total_size as usize, 0);
+        }
+
+        Memory { data }
+    }
+
+    pub fn get_size(&self) -> Word {
+        self.data.len() as Word
+    }
+
+    /// Masks byte with bitwise-and at addr
+    pub fn and_set_byte(&mut self, addr: Word, mask: Byte) {
+        if self.data.len() < addr as usize {
+            panic!("[MEMORY] and_set_byte: No such addr '0x{:08X}'", addr);
+        }
+
+        self.data[addr as usize] &= mask;
+    }
+
+    /// Masks word with bitwise-or at addr
+    pub fn or_set_byte(&mut self, addr: Word, mask: Byte) {
+        if self.data.len() < addr as usize {
+            panic!("[MEMORY] or_set_byte: No such addr '0x{:08X}'", addr);
+        }
+
+        self.data[addr as usize] |= mask;
+    }
+}
+
+#[allow(dead_code)]
+impl Device for Memory {
+    fn get_byte(&self, addr: Word) -> Byte {
+        *self
+            .data
+            .get(addr as usize)
+            .unwrap_or_else(|| panic!("[MEMORY] get_byte: No such addr '0x{:08X}'", addr))
+    }
+
+    fn get_word(&self, addr: Word) -> Word {
+        let data = self
+            .data
+            .get(addr as usize..addr as usize + 4)
+            .unwrap_or_else(|| panic!("[MEMORY] get_word: No such addr '0x{:08X}'", addr))
+            .try_into()
+            .expect("[MEMORY] get_word: Oddly sized Word");
+
+        Word::from_le_bytes(data)
+    }
+
+    fn get_range(&self, addr: Word, size: Word) -> Vec<Byte> {
+        self.data
+            .get(addr as usize..addr as usize + size as usize)
+            .unwrap_or_else(|| panic!("[MEMORY] get_range: No such addr range '0x{:08X}-{:08X}'", addr, addr + size))
+            .to_vec()
+    }
+
+    fn set_byte(&mut self, addr: Word, byte: Byte) {
+        if self.data.len() < addr as usize {
+            panic!("[MEMORY] set_byte: No such addr '0x{:08X}'", addr);
+        }
+
+        self.data[addr as usize] = byte;
+    }
+
+    fn set_word(&mut self, addr: Word, word: Word) {
+        if self.data.len() < addr as usize + 3 {
+            panic!("[MEMORY] set_word: No such addr '0x{:08X}'", addr);
+        }
+
+        for (i, byte) in word.to_le_bytes().iter().enumerate() {
+            self.data[addr as usize + i] = *byte;
+        }
+    }
+
+    fn set_range(&mut self, addr: Word, data: Vec<Byte>) {
+        if addr as usize + data.len() > self.data.len() {
+            panic!("[MEMORY] set_range: No such addr range '0x{:08X}-{:08X}'", addr, addr + data.len() as Word);
+        }
+
+        for (i, byte) in data.iter().enumerate() {
+            self.data[addr as usize + i] = *byte;
+        }
+    }
+}
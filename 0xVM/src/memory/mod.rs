@@ -1,2 +1,2 @@
-mod memory_struct; pub use memory_struct::*;
+mod memory_struct; pub use memory_struct::*;
 mod memory_mapper; pub use memory_mapper::*;
\ No newline at end of file